@@ -1,172 +1,118 @@
-use std::collections::HashSet;
+// The original Sub-Directory Mode front-end. It keeps its /FM and /SDM
+// switches, exact case-sensitive names and messages, but the searching is
+// done by filesearch-core, the same engine as the `filesearch` command.
 use std::env;
-use std::ffi::OsStr;
-use std::fs;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+
+use filesearch_core::{Results, SearchConfig, SearchEngine, SearchErrorKind, SearchEvent, SearchMode};
+
+// Files shown before asking whether to keep searching
+const PAGE_SIZE: usize = 10;
 
 struct FileSearch {
-    found_items: HashSet<PathBuf>,
+    results: Results,
+    events: Receiver<SearchEvent>,
 }
 
 impl FileSearch {
-    fn new() -> Self {
+    fn new(query: &str, directory: &Path, mode: SearchMode) -> Self {
+        let config = SearchConfig::builder(query)
+            .mode(mode)
+            .start_dir(directory)
+            .case_sensitive(true)
+            .wildcards(false)
+            .build();
+
+        let mut engine = SearchEngine::new(config);
+        let events = engine.subscribe();
+
         Self {
-            found_items: HashSet::new(),
+            results: engine.into_results(),
+            events,
         }
     }
 
-    fn search_file(&mut self, directory: &Path, filename: &str) -> Vec<PathBuf> {
-        let mut paths = Vec::new();
-
-        let entries = match fs::read_dir(directory) {
-            Ok(entries) => entries,
-            Err(e) => {
-                eprintln!("Error reading directory '{}': {}", directory.display(), e);
-                return paths;
-            }
-        };
-
-        for entry in entries {
-            let entry = match entry {
-                Ok(entry) => entry,
-                Err(_) => continue,
-            };
-
-            let file_type = match entry.file_type() {
-                Ok(ft) => ft,
-                Err(_) => continue,
-            };
-
-            let path = entry.path();
-
-            if file_type.is_dir() {
-                // Skip . and ..
-                let file_name = match entry.file_name().into_string() {
-                    Ok(name) => name,
-                    Err(_) => continue,
-                };
-
-                if file_name == "." || file_name == ".." {
-                    continue;
-                }
-
-                // Recursively search subdirectories
-                let mut subpaths = self.search_file(&path, filename);
-                paths.append(&mut subpaths);
-            } else if file_type.is_file() {
-                // Check if filename matches
-                if entry.file_name() == filename {
-                    if !self.found_items.contains(&path) {
-                        paths.push(path.clone());
-                        self.found_items.insert(path.clone());
-                        println!("Found file: {}", path.display());
-                    }
+    fn print_errors(&self) {
+        for event in self.events.try_iter() {
+            if let SearchEvent::Error(error) = event {
+                if error.kind == SearchErrorKind::ReadDir {
+                    eprintln!("Error reading directory '{}': {}", error.path.display(), error.error);
                 }
             }
         }
-
-        paths
     }
 
-    fn search_subdir(&mut self, directory: &Path, subdirname: &str) -> Vec<PathBuf> {
-        let mut paths = Vec::new();
+    fn file_mode(filename: &str, directory: &Path) {
+        let mut searcher = Self::new(filename, directory, SearchMode::File);
+        let mut found = 0;
+        let mut continued = false;
 
-        let entries = match fs::read_dir(directory) {
-            Ok(entries) => entries,
-            Err(e) => {
-                eprintln!("Error reading directory '{}': {}", directory.display(), e);
-                return paths;
+        loop {
+            // The search waits while the question is open, and picks up
+            // where it left off afterwards
+            let page = searcher.results.next_page(PAGE_SIZE);
+            searcher.print_errors();
+
+            for item in &page {
+                println!("Found file: {}", item.path.display());
+            }
+            found += page.len();
+
+            if page.len() < PAGE_SIZE {
+                break;
             }
-        };
-
-        for entry in entries {
-            let entry = match entry {
-                Ok(entry) => entry,
-                Err(_) => continue,
-            };
-
-            let file_type = match entry.file_type() {
-                Ok(ft) => ft,
-                Err(_) => continue,
-            };
-
-            if file_type.is_dir() {
-                let file_name = match entry.file_name().into_string() {
-                    Ok(name) => name,
-                    Err(_) => continue,
-                };
-
-                // Skip . and ..
-                if file_name == "." || file_name == ".." {
-                    continue;
-                }
 
-                let path = entry.path();
+            loop {
+                print!("Would you like to continue searching for more of the same file? (Y/N) ");
+                io::stdout().flush().unwrap();
 
-                // Check if directory name matches
-                if file_name == subdirname {
-                    if !self.found_items.contains(&path) {
-                        paths.push(path.clone());
-                        self.found_items.insert(path.clone());
-                        println!("Found subdirectory: {}", path.display());
-                    }
+                let mut response = String::new();
+                if io::stdin().read_line(&mut response).unwrap_or(0) == 0 {
+                    return;
                 }
+                let response = response.trim();
 
-                // Recursively search subdirectories
-                let mut subpaths = self.search_subdir(&path, subdirname);
-                paths.append(&mut subpaths);
+                if response.eq_ignore_ascii_case("Y") {
+                    continued = true;
+                    break;
+                } else if response.eq_ignore_ascii_case("N") {
+                    return;
+                } else {
+                    println!("Invalid response. Please enter 'Y' or 'N'.");
+                }
             }
         }
 
-        paths
-    }
-
-    fn file_mode(&mut self, filename: &str, directory: &Path) {
-        let mut results = self.search_file(directory, filename);
-
-        if results.is_empty() {
+        if found == 0 {
             eprintln!(
                 "No file(s) in this directory tree matched your query. Make sure that you spelled the query filename correctly.\n\
                 If your query filename contains spaces, you should have your query inside double-quotes (\") (e.g., \"Secret notes.txt\").\n\
                 Note: Query is CASE-SENSITIVE"
             );
-            return;
+        } else if continued {
+            println!("No more files found.");
         }
+    }
 
-        loop {
-            print!("Would you like to continue searching for more of the same file? (Y/N) ");
-            io::stdout().flush().unwrap();
-
-            let mut response = String::new();
-            io::stdin().read_line(&mut response).unwrap();
-            let response = response.trim();
+    fn subdir_mode(subdirname: &str, directory: &Path) {
+        let mut searcher = Self::new(subdirname, directory, SearchMode::Directory);
+        let mut found = 0;
 
-            if response.eq_ignore_ascii_case("Y") {
-                let new_results = self.search_file(directory, filename);
-                if new_results.is_empty() {
-                    println!("No more files found.");
-                    return;
-                }
-                results.extend(new_results);
-            } else if response.eq_ignore_ascii_case("N") {
-                return;
-            } else {
-                println!("Invalid response. Please enter 'Y' or 'N'.");
-            }
+        while let Some(item) = searcher.results.next() {
+            searcher.print_errors();
+            println!("Found subdirectory: {}", item.path.display());
+            found += 1;
         }
-    }
-
-    fn subdir_mode(&mut self, subdirname: &str, directory: &Path) {
-        let results = self.search_subdir(directory, subdirname);
+        searcher.print_errors();
 
-        if results.is_empty() {
+        if found == 0 {
             eprintln!(
                 "No subdirectory in this directory tree matched your query. Make sure that you spelled the query subdirectory correctly.\n\
                 If your query subdirectory contains spaces, you should have your query inside double-quotes (\") (e.g., \"My Documents\").\n\
                 Note: Query is CASE-SENSITIVE"
             );
-            return;
         }
     }
 }
@@ -207,11 +153,9 @@ fn main() {
         }
     };
 
-    let mut searcher = FileSearch::new();
-
     match mode.as_str() {
-        "/FM" => searcher.file_mode(query, &directory),
-        "/SDM" => searcher.subdir_mode(query, &directory),
+        "/FM" => FileSearch::file_mode(query, &directory),
+        "/SDM" => FileSearch::subdir_mode(query, &directory),
         "/?" | "/help" | "/h" => print_help(),
         _ => {
             eprintln!("Invalid mode. Use /FM for file mode or /SDM for subdirectory mode. Use /? for help");
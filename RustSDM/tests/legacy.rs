@@ -0,0 +1,114 @@
+// Runs the filesearch-sdm binary the way a person at the prompt would, with
+// the answers piped in.
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+// Must match PAGE_SIZE in main.rs
+const PAGE_SIZE: usize = 10;
+const PROMPT: &str = "Would you like to continue searching for more of the same file? (Y/N)";
+
+fn tmp_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("legacy").join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Each file sits in a directory of its own, so names differing only in
+// case can be told apart on case-insensitive file systems too
+fn make_files(root: &Path, names: &[&str]) {
+    for (index, name) in names.iter().enumerate() {
+        let dir = root.join(format!("d{}", index));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(name), "x").unwrap();
+    }
+}
+
+fn run(mode: &str, query: &str, root: &Path, input: &str) -> (String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_filesearch-sdm"))
+        .args([mode, query])
+        .arg(root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    (String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+}
+
+fn found(output: &str, kind: &str) -> Vec<String> {
+    let prefix = format!("Found {}: ", kind);
+    let mut names: Vec<String> = output.lines()
+        .flat_map(|line| line.split(PROMPT))
+        .filter_map(|line| line.trim_start().strip_prefix(&prefix))
+        .map(|path| Path::new(path).file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn file_mode_pages_until_told_to_stop() {
+    let root = tmp_dir("pages");
+    make_files(&root, &["target"; 25]);
+
+    let (stdout, _) = run("/FM", "target", &root, "Y\nN\n");
+    assert_eq!(found(&stdout, "file").len(), 2 * PAGE_SIZE);
+    assert_eq!(stdout.matches(PROMPT).count(), 2);
+    assert!(!stdout.contains("No more files found."));
+}
+
+#[test]
+fn file_mode_reports_the_end_after_continuing() {
+    let root = tmp_dir("end");
+    make_files(&root, &["target"; 25]);
+
+    let (stdout, _) = run("/FM", "target", &root, "y\nmaybe\nY\n");
+    assert_eq!(found(&stdout, "file").len(), 25);
+    assert_eq!(stdout.matches(PROMPT).count(), 3);
+    assert!(stdout.contains("Invalid response. Please enter 'Y' or 'N'."));
+    assert!(stdout.contains("No more files found."));
+}
+
+#[test]
+fn file_mode_matches_whole_names_exactly() {
+    let root = tmp_dir("exact");
+    make_files(&root, &["notes.txt", "Notes.txt", "NOTES.TXT", "notes.txt.bak", "my notes.txt", "notes"]);
+
+    let (stdout, _) = run("/FM", "notes.txt", &root, "");
+    assert_eq!(found(&stdout, "file"), ["notes.txt"]);
+
+    let (stdout, stderr) = run("/FM", "Notes.TXT", &root, "");
+    assert!(found(&stdout, "file").is_empty());
+    assert!(stderr.contains("Note: Query is CASE-SENSITIVE"));
+}
+
+#[cfg(unix)]
+#[test]
+fn wildcard_characters_are_part_of_the_name() {
+    let root = tmp_dir("literal");
+    make_files(&root, &["a*c", "abc", "a?c"]);
+
+    let (stdout, _) = run("/FM", "a*c", &root, "");
+    assert_eq!(found(&stdout, "file"), ["a*c"]);
+    let (stdout, _) = run("/FM", "a?c", &root, "");
+    assert_eq!(found(&stdout, "file"), ["a?c"]);
+}
+
+#[test]
+fn subdirectory_mode_matches_whole_names_exactly() {
+    let root = tmp_dir("subdirs");
+    for dir in ["one/logs", "two/Logs", "three/logs2", "four/old logs"] {
+        fs::create_dir_all(root.join(dir)).unwrap();
+    }
+    make_files(&root, &["logs"]);
+
+    let (stdout, _) = run("/SDM", "logs", &root, "");
+    assert_eq!(found(&stdout, "subdirectory"), ["logs"]);
+    assert!(found(&stdout, "file").is_empty());
+}
@@ -0,0 +1,19 @@
+// Records what the binary was built for and with, for `--version --verbose`.
+use std::env;
+use std::process::Command;
+
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let compiler = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    
+    println!("cargo:rustc-env=FILESEARCH_TARGET={}", env::var("TARGET").unwrap_or_default());
+    println!("cargo:rustc-env=FILESEARCH_PROFILE={}", env::var("PROFILE").unwrap_or_default());
+    println!("cargo:rustc-env=FILESEARCH_RUSTC={}", compiler);
+    println!("cargo:rerun-if-changed=build.rs");
+}
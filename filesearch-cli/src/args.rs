@@ -0,0 +1,1070 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use filesearch_core::{
+    known_kinds, mime_for_kind, system_description, Checkpoint, Expression, FileKind, MatchTarget, Normalization,
+    PermissionFilter, SearchConfig, SearchMode,
+};
+
+use crate::config_file::{load_config_files, Preset, PROJECT_CONFIG_NAME};
+use crate::{EXIT_FOUND, EXIT_INCOMPLETE, EXIT_IO_ERRORS, EXIT_NOT_FOUND, EXIT_USAGE};
+
+/// Everything given on the command line and in config files. The parts
+/// that define the search itself become a `SearchConfig`.
+#[derive(Clone)]
+pub struct Options {
+    pub query: String,
+    // Only set when resuming a checkpoint written by another front-end
+    pub alternatives: Vec<String>,
+    pub start_dirs: Vec<PathBuf>,
+    pub mode: SearchMode,
+    pub max_threads: usize,
+    pub use_wildcards: bool,
+    pub case_sensitive: bool,
+    // Case-insensitive unless the query has an uppercase letter
+    pub smart_case: bool,
+    pub normalization: Normalization,
+    pub ignore_accents: bool,
+    pub fuzzy: bool,
+    pub match_target: MatchTarget,
+    pub extensions: Vec<String>,
+    pub file_kinds: Vec<FileKind>,
+    pub only_empty: bool,
+    pub only_executable: bool,
+    pub user: Option<u32>,
+    pub group: Option<u32>,
+    pub no_user: bool,
+    pub no_group: bool,
+    pub permissions: Option<PermissionFilter>,
+    pub mime_types: Vec<String>,
+    // The query is an expression like `name:*.log AND size>10M`
+    pub expression: bool,
+    pub log_path: Option<PathBuf>,
+    pub max_depth: Option<usize>,
+    pub breadth_first: bool,
+    pub checkpoint_path: Option<PathBuf>,
+    pub resume_path: Option<PathBuf>,
+    pub timeout: Option<Duration>,
+    pub output: OutputMode,
+    // Pause after this many matches and ask before showing more
+    pub page_size: Option<usize>,
+    pub save_policy: SavePolicy,
+    pub paths_from: Option<PathBuf>,
+    pub walk_listed_dirs: bool,
+    pub record_history: bool,
+}
+
+impl Options {
+    /// Whether progress, matches and the summary go to stdout.
+    pub fn is_verbose(&self) -> bool {
+        self.output == OutputMode::Normal
+    }
+    
+    pub fn shows_warnings(&self) -> bool {
+        self.output != OutputMode::Quiet
+    }
+    
+    /// Takes the settings that define the search from a checkpoint being resumed.
+    pub fn apply_checkpoint(&mut self, checkpoint: &Checkpoint) {
+        self.mode = checkpoint.mode;
+        self.query = checkpoint.query.clone();
+        self.alternatives = checkpoint.alternatives.clone();
+        self.start_dirs = checkpoint.start_dirs.clone();
+        self.case_sensitive = checkpoint.case_sensitive;
+        self.smart_case = checkpoint.smart_case;
+        self.normalization = checkpoint.normalization;
+        self.ignore_accents = checkpoint.ignore_accents;
+        self.use_wildcards = checkpoint.use_wildcards;
+        self.fuzzy = checkpoint.fuzzy;
+        self.match_target = checkpoint.match_target;
+        self.extensions = checkpoint.extensions.clone();
+        self.file_kinds = checkpoint.file_kinds.clone();
+        self.only_empty = checkpoint.only_empty;
+        self.only_executable = checkpoint.only_executable;
+        self.user = checkpoint.user;
+        self.group = checkpoint.group;
+        self.no_user = checkpoint.no_user;
+        self.no_group = checkpoint.no_group;
+        self.permissions = checkpoint.permissions;
+        self.mime_types = checkpoint.mime_types.clone();
+        self.expression = checkpoint.expression.is_some();
+        if let Some(ref expression) = checkpoint.expression {
+            self.query = expression.source().to_string();
+        }
+        self.max_depth = checkpoint.max_depth;
+        self.breadth_first = checkpoint.breadth_first;
+    }
+    
+    pub fn search_config(&self) -> SearchConfig {
+        // The fuzzy finder collects every name and ranks them as the user
+        // types, so the walk itself matches everything
+        let finder = self.fuzzy && self.output == OutputMode::Tui;
+        let (query, alternatives) = if finder {
+            (String::new(), Vec::new())
+        } else {
+            (self.query.clone(), self.alternatives.clone())
+        };
+        
+        let mut builder = SearchConfig::builder(query)
+            .alternatives(alternatives)
+            .mode(self.mode)
+            .start_dirs(self.start_dirs.clone())
+            .threads(self.max_threads)
+            .case_sensitive(self.case_sensitive)
+            .smart_case(self.smart_case)
+            .normalization(self.normalization)
+            .ignore_accents(self.ignore_accents)
+            .wildcards(self.use_wildcards)
+            .fuzzy(self.fuzzy)
+            .match_on(self.match_target)
+            .extensions(self.extensions.clone())
+            .file_kinds(self.file_kinds.clone())
+            .only_empty(self.only_empty)
+            .only_executable(self.only_executable)
+            .user(self.user)
+            .group(self.group)
+            .no_user(self.no_user)
+            .no_group(self.no_group)
+            .permissions(self.permissions)
+            .mime_types(self.mime_types.clone())
+            .expression(self.expression.then(|| Expression::parse(&self.query).ok()).flatten())
+            .max_depth(self.max_depth)
+            .breadth_first(self.breadth_first)
+            .log_path(self.log_path.clone())
+            .checkpoint_path(self.checkpoint_path.clone())
+            .timeout(self.timeout);
+        // The status bar should keep up with the list
+        if self.output == OutputMode::Tui {
+            builder = builder.progress_interval(Duration::from_millis(250));
+        }
+        builder.build()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum OutputMode {
+    Normal,
+    Quiet,
+    Count,
+    // Print the paths as they are, each ended by a NUL byte, for xargs -0
+    Print0,
+    // Browse the results in a full-screen list as they arrive
+    Tui,
+    // One JSON object or CSV row per match, with its size and detected type
+    Json,
+    Csv,
+}
+
+/// What to do with the results once the search is over.
+#[derive(Clone, PartialEq)]
+pub enum SavePolicy {
+    Ask,
+    Never,
+    Desktop,
+    Path(PathBuf),
+}
+
+pub struct OptionSpec {
+    pub long: &'static str,
+    pub short: Option<char>,
+    pub takes_value: bool,
+}
+
+const fn flag(long: &'static str, short: Option<char>) -> OptionSpec {
+    OptionSpec { long, short, takes_value: false }
+}
+
+const fn valued(long: &'static str, short: Option<char>) -> OptionSpec {
+    OptionSpec { long, short, takes_value: true }
+}
+
+// Every option the parser knows about; the mode switches are here too so
+// `-f`, `-d` and `-b` can be combined with other short flags
+pub const OPTIONS: &[OptionSpec] = &[
+    flag("file", Some('f')),
+    flag("dir", Some('d')),
+    flag("both", Some('b')),
+    flag("help", Some('h')),
+    flag("version", Some('V')),
+    flag("verbose", Some('v')),
+    valued("threads", Some('t')),
+    valued("log", Some('l')),
+    flag("case-insensitive", Some('i')),
+    flag("smart-case", None),
+    valued("normalize", None),
+    flag("ignore-accents", None),
+    valued("depth", Some('D')),
+    flag("dfs", None),
+    flag("no-wildcards", None),
+    flag("fuzzy", None),
+    valued("match-on", None),
+    valued("ext", None),
+    valued("type", None),
+    flag("empty", None),
+    flag("executable", None),
+    valued("user", None),
+    valued("group", None),
+    flag("nouser", None),
+    flag("nogroup", None),
+    valued("perm", None),
+    valued("mime", None),
+    valued("kind", None),
+    flag("expr", None),
+    valued("checkpoint", None),
+    valued("resume", None),
+    valued("timeout", None),
+    flag("quiet", Some('q')),
+    flag("count", None),
+    flag("print0", Some('0')),
+    flag("tui", None),
+    flag("json", None),
+    flag("csv", None),
+    valued("page", None),
+    flag("no-prompt", None),
+    flag("yes", Some('y')),
+    valued("save", None),
+    valued("paths-from", None),
+    flag("no-walk", None),
+    valued("profile", None),
+    flag("no-config", None),
+    flag("no-history", None),
+];
+
+fn find_long(name: &str) -> Result<&'static OptionSpec, String> {
+    OPTIONS.iter()
+        .find(|spec| spec.long == name)
+        .ok_or_else(|| format!("Unknown option: --{}", name))
+}
+
+fn find_short(c: char) -> Result<&'static OptionSpec, String> {
+    OPTIONS.iter()
+        .find(|spec| spec.short == Some(c))
+        .ok_or_else(|| format!("Unknown option: -{}", c))
+}
+
+/// The original `/FM`-style switches, matched case-insensitively.
+fn slash_option(arg: &str) -> Option<&'static str> {
+    match arg.to_ascii_uppercase().as_str() {
+        "/FM" => Some("file"),
+        "/SDM" => Some("dir"),
+        "/BOTH" => Some("both"),
+        "/?" | "/HELP" => Some("help"),
+        _ => None,
+    }
+}
+
+pub fn parse_arguments(args: &[String]) -> Result<Options, String> {
+    if args.is_empty() {
+        return Err("Insufficient arguments".to_string());
+    }
+    
+    let num_cpus = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    
+    let mut config = Options {
+        query: String::new(),
+        alternatives: Vec::new(),
+        start_dirs: Vec::new(),
+        mode: SearchMode::Both,
+        max_threads: num_cpus,
+        use_wildcards: true,
+        case_sensitive: true,
+        smart_case: false,
+        normalization: Normalization::None,
+        ignore_accents: false,
+        fuzzy: false,
+        match_target: MatchTarget::Name,
+        extensions: Vec::new(),
+        file_kinds: Vec::new(),
+        only_empty: false,
+        only_executable: false,
+        user: None,
+        group: None,
+        no_user: false,
+        no_group: false,
+        permissions: None,
+        mime_types: Vec::new(),
+        expression: false,
+        log_path: None,
+        max_depth: None,
+        breadth_first: true,
+        checkpoint_path: None,
+        resume_path: None,
+        timeout: None,
+        output: OutputMode::Normal,
+        page_size: None,
+        save_policy: SavePolicy::Ask,
+        paths_from: None,
+        walk_listed_dirs: true,
+        record_history: true,
+    };
+    
+    let mut options: Vec<(&OptionSpec, Option<String>)> = Vec::new();
+    let mut positionals = Vec::new();
+    let mut options_ended = false;
+    let mut i = 0;
+    
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+        
+        if options_ended || arg == "-" || !(arg.starts_with('-') || slash_option(arg).is_some()) {
+            positionals.push(arg.clone());
+            continue;
+        }
+        
+        if arg == "--" {
+            options_ended = true;
+            continue;
+        }
+        
+        // Collect (option, value) pairs first, so `-iD 3` and `--depth=3`
+        // go through the same code as their long forms
+        if let Some(long) = slash_option(arg) {
+            options.push((find_long(long)?, None));
+        } else if let Some(body) = arg.strip_prefix("--") {
+            let (name, inline_value) = match body.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (body, None),
+            };
+            let spec = find_long(name)?;
+            
+            let value = if !spec.takes_value {
+                if inline_value.is_some() {
+                    return Err(format!("Option --{} doesn't take a value", spec.long));
+                }
+                None
+            } else if inline_value.is_some() {
+                inline_value
+            } else if i < args.len() {
+                i += 1;
+                Some(args[i - 1].clone())
+            } else {
+                return Err(format!("Option --{} requires a value", spec.long));
+            };
+            options.push((spec, value));
+        } else if arg == "-nw" {
+            // Kept for scripts written against older versions
+            options.push((find_long("no-wildcards")?, None));
+        } else {
+            let flags = &arg[1..];
+            for (offset, c) in flags.char_indices() {
+                let spec = find_short(c)?;
+                if !spec.takes_value {
+                    options.push((spec, None));
+                    continue;
+                }
+                
+                // A value either follows the letter directly (-t8) or is the next argument
+                let rest = &flags[offset + c.len_utf8()..];
+                let value = if !rest.is_empty() {
+                    rest.to_string()
+                } else if i < args.len() {
+                    i += 1;
+                    args[i - 1].clone()
+                } else {
+                    return Err(format!("Option -{} requires a value", c));
+                };
+                options.push((spec, Some(value)));
+                break;
+            }
+        }
+    }
+    
+    if options.iter().any(|(spec, _)| spec.long == "help") {
+        print_help();
+        std::process::exit(0);
+    }
+    
+    if options.iter().any(|(spec, _)| spec.long == "version") {
+        print_version(options.iter().any(|(spec, _)| spec.long == "verbose"));
+        std::process::exit(0);
+    }
+    if options.iter().any(|(spec, _)| spec.long == "verbose") {
+        return Err("--verbose only applies to --version".to_string());
+    }
+    
+    // Config files are applied first so anything on the command line wins
+    let profile = options.iter().rev()
+        .find(|(spec, _)| spec.long == "profile")
+        .and_then(|(_, value)| value.clone());
+    let mut preset = Preset::default();
+    if options.iter().any(|(spec, _)| spec.long == "no-config") {
+        if profile.is_some() {
+            return Err("--profile can't be used with --no-config".to_string());
+        }
+    } else {
+        load_config_files(profile.as_deref(), &mut config, &mut preset)?;
+    }
+    
+    let mut mode = None;
+    
+    for (spec, value) in options {
+        let new_mode = match spec.long {
+            "file" => SearchMode::File,
+            "dir" => SearchMode::Directory,
+            "both" => SearchMode::Both,
+            _ => {
+                apply_option(&mut config, spec.long, value.as_deref())?;
+                continue;
+            }
+        };
+        
+        if mode.is_some_and(|m| m != new_mode) {
+            return Err("Only one search mode (/FM, /SDM or /BOTH) can be given".to_string());
+        }
+        mode = Some(new_mode);
+    }
+    
+    // A resumed search takes its mode, query and directory from the checkpoint
+    if config.resume_path.is_some() {
+        if mode.is_some() || !positionals.is_empty() {
+            return Err("--resume takes the mode, query and directory from the checkpoint; only options can be given".to_string());
+        }
+        return Ok(config);
+    }
+    
+    config.mode = mode.or(preset.mode)
+        .ok_or("No search mode given (use /FM, /SDM, /BOTH or -f, -d, -b)")?;
+    check_mode_filters(&config)?;
+    
+    // Positional arguments replace the query and directories from a profile
+    let mut positionals = positionals.into_iter();
+    config.query = positionals.next().or(preset.query).ok_or("No search query given")?;
+    if config.expression {
+        if config.fuzzy {
+            return Err("--expr can't be used with --fuzzy".to_string());
+        }
+        Expression::parse(&config.query).map_err(|e| format!("Invalid expression: {}", e))?;
+    }
+    config.start_dirs = positionals.map(PathBuf::from).collect();
+    if config.start_dirs.is_empty() {
+        config.start_dirs = preset.dirs;
+    }
+    if config.start_dirs.is_empty() && config.paths_from.is_none() {
+        config.start_dirs.push(env::current_dir().map_err(|e| e.to_string())?);
+    }
+    
+    Ok(config)
+}
+
+// File mode finds everything but directories and directory mode only
+// directories, so some filters could never match
+fn check_mode_filters(config: &Options) -> Result<(), String> {
+    let (mode, finds) = match config.mode {
+        SearchMode::File => ("/FM (-f)", "everything but directories"),
+        SearchMode::Directory => ("/SDM (-d)", "only directories"),
+        SearchMode::Both => return Ok(()),
+    };
+    
+    let unreachable: Vec<String> = config.file_kinds.iter()
+        .filter(|&&kind| (kind == FileKind::Directory) == (config.mode == SearchMode::File))
+        .map(|kind| kind.letter().to_string())
+        .collect();
+    if !unreachable.is_empty() {
+        return Err(format!("--type {} can't be used with {}, which finds {}", unreachable.join(","), mode, finds));
+    }
+    if config.only_executable && config.mode == SearchMode::Directory {
+        return Err(format!("--executable can't be used with {}, which finds {}", mode, finds));
+    }
+    Ok(())
+}
+
+pub fn apply_option(config: &mut Options, name: &str, value: Option<&str>) -> Result<(), String> {
+    // The parser guarantees valued options have a value
+    let value = value.unwrap_or_default();
+    
+    match name {
+        "threads" => {
+            config.max_threads = match value.parse() {
+                Ok(threads) if threads > 0 => threads,
+                _ => return Err(format!("Invalid value '{}' for --threads: expected a positive number", value)),
+            };
+        }
+        "log" => config.log_path = Some(PathBuf::from(value)),
+        "case-insensitive" => {
+            config.case_sensitive = false;
+            config.smart_case = false;
+        }
+        "smart-case" => config.smart_case = true,
+        "normalize" => {
+            config.normalization = match value.to_ascii_lowercase().as_str() {
+                "nfc" => Normalization::Nfc,
+                "nfd" => Normalization::Nfd,
+                "none" => Normalization::None,
+                _ => return Err(format!("Invalid value '{}' for --normalize: expected nfc, nfd or none", value)),
+            };
+        }
+        "ignore-accents" => config.ignore_accents = true,
+        "depth" => {
+            config.max_depth = Some(value.parse().map_err(|_| {
+                format!("Invalid value '{}' for --depth: expected a whole number", value)
+            })?);
+        }
+        "dfs" => config.breadth_first = false,
+        "no-wildcards" => config.use_wildcards = false,
+        "fuzzy" => config.fuzzy = true,
+        "expr" => config.expression = true,
+        "match-on" => {
+            config.match_target = match value {
+                "name" => MatchTarget::Name,
+                "path" => MatchTarget::Path,
+                "relpath" => MatchTarget::RelativePath,
+                "stem" => MatchTarget::Stem,
+                "ext" => MatchTarget::Extension,
+                _ => return Err(format!("Invalid value '{}' for --match-on: expected name, path, relpath, stem or ext", value)),
+            };
+        }
+        "ext" => {
+            let extensions: Vec<String> = value.split(',')
+                .map(str::trim)
+                .filter(|extension| !extension.is_empty())
+                .map(str::to_string)
+                .collect();
+            if extensions.is_empty() {
+                return Err(format!("Invalid value '{}' for --ext: expected extensions like rs,toml", value));
+            }
+            config.extensions.extend(extensions);
+        }
+        "type" => {
+            let kinds: Option<Vec<FileKind>> = value.split(',')
+                .map(str::trim)
+                .filter(|letter| !letter.is_empty())
+                .map(|letter| {
+                    let mut chars = letter.chars();
+                    chars.next().and_then(FileKind::from_letter).filter(|_| chars.next().is_none())
+                })
+                .collect();
+            match kinds {
+                Some(kinds) if !kinds.is_empty() => config.file_kinds.extend(kinds),
+                _ => return Err(format!("Invalid value '{}' for --type: expected letters f, d, l, p, s, b or c", value)),
+            }
+        }
+        "empty" => config.only_empty = true,
+        "executable" => config.only_executable = true,
+        "user" => config.user = Some(parse_account("user", value)?),
+        "group" => config.group = Some(parse_account("group", value)?),
+        "nouser" => config.no_user = true,
+        "nogroup" => config.no_group = true,
+        "mime" => {
+            let mime_types: Vec<String> = value.split(',')
+                .map(str::trim)
+                .filter(|mime| !mime.is_empty())
+                .map(str::to_string)
+                .collect();
+            if mime_types.is_empty() {
+                return Err(format!("Invalid value '{}' for --mime: expected types like image/* or application/pdf", value));
+            }
+            config.mime_types.extend(mime_types);
+        }
+        "kind" => {
+            let mime_types: Option<Vec<String>> = value.split(',')
+                .map(str::trim)
+                .filter(|kind| !kind.is_empty())
+                .map(|kind| mime_for_kind(kind).map(str::to_string))
+                .collect();
+            match mime_types {
+                Some(mime_types) if !mime_types.is_empty() => config.mime_types.extend(mime_types),
+                _ => return Err(format!("Invalid value '{}' for --kind: expected kinds from {}", value, known_kinds().join(", "))),
+            }
+        }
+        "perm" => {
+            config.permissions = Some(PermissionFilter::parse(value)
+                .map_err(|e| format!("Invalid value '{}' for --perm: {}", value, e))?);
+        }
+        "checkpoint" => config.checkpoint_path = Some(PathBuf::from(value)),
+        "resume" => config.resume_path = Some(PathBuf::from(value)),
+        "timeout" => config.timeout = Some(parse_duration(value)?),
+        "quiet" => config.output = OutputMode::Quiet,
+        "count" => config.output = OutputMode::Count,
+        "print0" => config.output = OutputMode::Print0,
+        "tui" => config.output = OutputMode::Tui,
+        "json" => config.output = OutputMode::Json,
+        "csv" => config.output = OutputMode::Csv,
+        "page" => {
+            config.page_size = match value.parse() {
+                Ok(size) if size > 0 => Some(size),
+                _ => return Err(format!("Invalid value '{}' for --page: expected a positive number", value)),
+            };
+        }
+        "no-prompt" => config.save_policy = SavePolicy::Never,
+        "yes" => config.save_policy = SavePolicy::Desktop,
+        "save" => config.save_policy = SavePolicy::Path(PathBuf::from(value)),
+        "paths-from" => config.paths_from = Some(PathBuf::from(value)),
+        "no-walk" => config.walk_listed_dirs = false,
+        "no-history" => config.record_history = false,
+        // Handled before any other option is applied
+        "profile" | "no-config" => {}
+        _ => return Err(format!("Unknown option: --{}", name)),
+    }
+    
+    Ok(())
+}
+
+/// Undoes a flag, for `flag = false` in a config file overriding a broader one.
+pub fn reset_option(config: &mut Options, name: &str) {
+    match name {
+        "case-insensitive" => config.case_sensitive = true,
+        "smart-case" => config.smart_case = false,
+        "ignore-accents" => config.ignore_accents = false,
+        "dfs" => config.breadth_first = true,
+        "no-wildcards" => config.use_wildcards = true,
+        "fuzzy" => config.fuzzy = false,
+        "expr" => config.expression = false,
+        "empty" => config.only_empty = false,
+        "executable" => config.only_executable = false,
+        "nouser" => config.no_user = false,
+        "nogroup" => config.no_group = false,
+        "quiet" | "count" | "print0" | "tui" | "json" | "csv" => config.output = OutputMode::Normal,
+        "no-prompt" | "yes" => config.save_policy = SavePolicy::Ask,
+        "no-walk" => config.walk_listed_dirs = true,
+        "no-history" => config.record_history = true,
+        _ => {}
+    }
+}
+
+// --user, --group, --nouser and --nogroup
+const ACCOUNTS_SUPPORTED: bool = cfg!(unix);
+
+/// Resolves a user or group name to its ID; a number is taken as an ID.
+#[cfg(unix)]
+fn parse_account(kind: &str, name: &str) -> Result<u32, String> {
+    let id = match kind {
+        "user" => filesearch_core::user_id(name),
+        _ => filesearch_core::group_id(name),
+    };
+    id.ok_or_else(|| format!("Invalid value '{}' for --{}: no such {}", name, kind, kind))
+}
+
+#[cfg(not(unix))]
+fn parse_account(kind: &str, _name: &str) -> Result<u32, String> {
+    Err(format!("--{} is only supported on Unix", kind))
+}
+
+/// Parses durations like `30s`, `1.5m`, `2h` or `500ms`; a bare number is seconds.
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let split = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    
+    let value: f64 = number.parse()
+        .map_err(|_| format!("Invalid duration: {}", text))?;
+    let seconds = match unit {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        _ => return Err(format!("Invalid duration unit in '{}' (use ms, s, m or h)", text)),
+    };
+    
+    Duration::try_from_secs_f64(seconds)
+        .map_err(|_| format!("Invalid duration: {}", text))
+}
+
+/// `--version`, and with `--verbose` what the binary was built for and
+/// the platform it's running on, for bug reports.
+fn print_version(verbose: bool) {
+    println!("filesearch {}", env!("CARGO_PKG_VERSION"));
+    if !verbose {
+        return;
+    }
+    
+    // Each is whatever the platform-specific code compiled in says
+    let features: Vec<&str> = [
+        ("tui", crate::tui::SUPPORTED),
+        ("ctrl-c", crate::interrupt::SUPPORTED),
+        ("owners", ACCOUNTS_SUPPORTED),
+    ]
+        .into_iter()
+        .filter_map(|(name, supported)| supported.then_some(name))
+        .collect();
+    
+    println!("Build target: {} ({})", env!("FILESEARCH_TARGET"), env!("FILESEARCH_PROFILE"));
+    println!("Compiler: {}", env!("FILESEARCH_RUSTC"));
+    println!("Features: {}", if features.is_empty() { "none".to_string() } else { features.join(", ") });
+    println!("Platform: {}", system_description());
+    println!("CPU cores: {}", std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1));
+}
+
+pub fn print_help() {
+    println!("Filesearch - Revised");
+    println!("Created by EndrDragon44");
+    println!("{}", system_description());
+    println!();
+    println!("USAGE:");
+    println!("  filesearch [mode] [query] [search from dir...] [flags]");
+    println!("  filesearch --resume [checkpoint file] [flags]");
+    println!("  filesearch history [N]            List the last N searches (default 20)");
+    println!("  filesearch rerun [id|name] [flags] Run a previous or saved search again");
+    println!("  filesearch save [name] [id]       Save a search (default: the last one) by name");
+    println!();
+    println!("  Flags can go anywhere on the line. Long options also accept --option=value,");
+    println!("  short flags can be combined (-iD 3 is -i -D 3), and -- ends the options so");
+    println!("  a query or directory starting with - can be given.");
+    println!();
+    println!("MODES:");
+    println!("  /FM, -f, --file     Search for files only");
+    println!("  /SDM, -d, --dir     Search for directories only");
+    println!("  /BOTH, -b, --both   Search for both files and directories");
+    println!("        This mode doesn't require a file extension to match an object.");
+    println!("  /?, -h, --help      Show this help message");
+    println!("  -V, --version       Show the version; add --verbose (-v) for build and platform details");
+    println!();
+    println!("PATTERN SYNTAX:");
+    println!("  *.txt              All text files (wildcards: *, ?)");
+    println!("  report*.pdf        Files starting with 'report' and ending .pdf");
+    println!("  image_??.jpg       Files like image_01.jpg, image_AB.jpg");
+    println!("  document           Exact match 'document'");
+    println!("  caf\\xe9.txt        A name that isn't valid UTF-8: \\xNN is a raw byte.");
+    println!("                     Such names are shown in the same form.");
+    println!("  what\\?.txt         A literal * or ? is escaped as \\* or \\?. Other backslashes");
+    println!("                     are kept as they are, so C:\\dir needs no escaping.");
+    println!();
+    println!("OPTIONS:");
+    println!("  --threads N, -t N    Number of threads (default: CPU cores)");
+    println!("  --log FILE, -l FILE  Save results to log file");
+    println!("  --case-insensitive, -i  Case-insensitive search, with full Unicode case folding (ß = SS)");
+    println!("  --smart-case         Case-insensitive unless the query has an uppercase letter");
+    println!("  --normalize FORM     Compare names in Unicode form nfc or nfd, so accented letters");
+    println!("                       match however they were typed or stored (default: none)");
+    println!("  --ignore-accents     Ignore accents and spell out ligatures (Resume*.docx finds Résumé.docx)");
+    println!("  --depth N, -D N      Maximum directory depth, counted from each start directory");
+    println!("  --dfs                Use Depth-First Search (default: BFS)");
+    println!("  --no-wildcards, -nw  Treat * and ? as literal characters");
+    println!("  --fuzzy              Match names containing the query's letters in order, best first;");
+    println!("                       with --tui, type to rank everything found so far");
+    println!("  --expr               The query is an expression; see EXPRESSIONS below");
+    println!("  --match-on PART      Match the query against the name (default), path, relpath");
+    println!("                       (below the start directory), stem (name without extension) or ext");
+    println!("  --ext LIST           Only match names with one of these extensions, e.g. rs,toml or tar.gz;");
+    println!("                       case-insensitive, and can be given more than once");
+    println!("  --type LIST          Only match these kinds, as in find -type: f file, d directory,");
+    println!("                       l symlink, p pipe, s socket, b block and c character device;");
+    println!("                       e.g. --type f,l. Kinds the mode can't find (d with -f, anything");
+    println!("                       but d with -d) are an error");
+    println!("  --empty              Only match empty files and empty directories");
+    println!("  --executable         Only match files with execute permission (.exe, .bat etc. on Windows)");
+    println!("  --user NAME          Only match entries owned by this user name or ID (Unix only)");
+    println!("  --group NAME         Only match entries in this group name or ID (Unix only)");
+    println!("  --nouser, --nogroup  Only match entries whose owner or group no longer exists (Unix only)");
+    println!("  --perm MODE          Only match these permission bits, in octal (644) or symbolic (u=rw,go=r)");
+    println!("                       form: exactly, all of them with -MODE or any of them with /MODE");
+    println!("                       A symlink's owner, group and mode are the link's own, as in find");
+    println!("  --mime LIST          Only match files whose first bytes show one of these MIME types,");
+    println!("                       e.g. image/* or application/pdf; can be given more than once");
+    println!("  --kind LIST          The same by short name, e.g. pdf,png,zip,elf,sqlite");
+    println!("  --checkpoint FILE    Periodically save progress so the search can be resumed");
+    println!("  --resume FILE        Continue a search from a checkpoint file");
+    println!("  --timeout TIME       Stop after TIME (e.g. 30s, 5m, 1h) and exit with code {}", EXIT_INCOMPLETE);
+    println!("  --quiet, -q          Print nothing; only the exit code tells the result");
+    println!("  --count              Print only the number of matches");
+    println!("  --print0, -0         Print only the paths, exactly as stored, each ended by a NUL byte");
+    println!("  --json, --csv        Print one JSON object or CSV row per match: path, type, size, the");
+    println!("                       MIME type its first bytes show and the system it was found on");
+    println!("  --page N             Pause after every N matches until asked for more (interactive only)");
+    println!("  --tui                Browse matches live in a full-screen list; / refines the list,");
+    println!("                       Enter opens, c copies the path, r reveals, d deletes, q quits");
+    println!("  --save FILE          Save the results to FILE without asking");
+    println!("  --yes, -y            Save the results to the desktop without asking");
+    println!("  --no-prompt          Never ask to save the results (default when not run from a terminal)");
+    println!("  --paths-from FILE    Read paths from FILE (- for stdin), one per line or NUL-separated;");
+    println!("                       directories are searched, other paths are matched directly");
+    println!("  --no-walk            With --paths-from, match listed directories instead of searching them");
+    println!("  --profile NAME       Use a named profile from a config file");
+    println!("  --no-config          Ignore config files");
+    println!("  --no-history         Don't record this search in the history");
+    println!();
+    println!("EXAMPLES:");
+    println!("  Basic usage:");
+    println!("    filesearch /FM *.txt .");
+    println!("    filesearch /SDM Documents ~");
+    println!("    filesearch -f *.conf /etc /usr/local/etc ~/.config");
+    println!("    git ls-files -z | filesearch -f *.rs --paths-from -");
+    println!();
+    println!("  Multi-threaded searches:");
+    println!("    filesearch /FM *.rs . --threads 8");
+    println!("    filesearch /BOTH *config* . -i --threads 4");
+    println!("    filesearch -it4 -b *config* .");
+    println!();
+    println!("  With logging and depth limits:");
+    println!("    filesearch /FM *.tmp C:\\Users --depth 2 --log cleanup.txt");
+    println!("    filesearch /SDM log /var --depth 3 --log system_logs.txt");
+    println!();
+    println!("  Long-running searches:");
+    println!("    filesearch /FM *.iso /mnt/archive --checkpoint archive.ckpt");
+    println!("    filesearch --resume archive.ckpt");
+    println!();
+    println!("  Matching paths and extensions:");
+    println!("    filesearch -f \"*/config/*.yaml\" . --match-on relpath");
+    println!("    filesearch -f \"*\" ~/src --ext rs,toml");
+    println!("    filesearch -f main --match-on stem --ext rs,c .");
+    println!("    filesearch -b \"*\" /tmp --type p,s");
+    println!("    filesearch -b \"*\" ~/src --empty");
+    println!();
+    println!("  Fuzzy matching:");
+    println!("    filesearch -f --fuzzy -i srchcfg .");
+    println!("    filesearch --tui --fuzzy \"\" ~/projects");
+    println!();
+    println!("  File types by content:");
+    println!("    filesearch -f \"*\" ~/Downloads --mime image/*");
+    println!("    filesearch -f \"*.txt\" . --kind zip,gzip --json");
+    println!();
+    println!("  Security audits:");
+    println!("    filesearch -f \"*\" /usr --perm -4000        # setuid programs");
+    println!("    filesearch -b \"*\" /srv --perm /o+w         # world-writable");
+    println!("    filesearch -b \"*\" /home --nouser");
+    println!();
+    println!("  Cross-platform examples:");
+    println!("    filesearch /FM *.exe C:\\Windows");
+    println!("    filesearch /SDM Applications /Applications");
+    println!("    filesearch /BOTH backup . --log all_backups.txt");
+    println!();
+    println!("EXPRESSIONS:");
+    println!("  With --expr the query combines tests with AND, OR, NOT and parentheses:");
+    println!("    filesearch -f --expr \"name:*.log AND size>10M AND (mtime<7d OR owner:www-data) AND NOT path:*/archive/*\" /var");
+    println!();
+    println!("  name:P, path:P, relpath:P, stem:P  Match that part of the path against pattern P");
+    println!("  ext:rs,toml        The name has one of these extensions (like --ext)");
+    println!("  type:f,l           It's one of these kinds (like --type)");
+    println!("  size>10M           Size compared with <, <=, =, >= or >; units B, K, M, G, T");
+    println!("  mtime<7d           Modified less (<) or more (>) than this long ago; units s, m, h, d, w");
+    println!("  owner:NAME         Owned by this user or user ID (Unix only)");
+    println!("  group:NAME         In this group or group ID (Unix only)");
+    println!("  perm:MODE          Has these permission bits (like --perm)");
+    println!("  mime:TYPE, kind:K  The first bytes show this MIME type or kind (like --mime, --kind)");
+    println!("  content:TEXT       The file contains TEXT (-i ignores case for ASCII letters)");
+    println!("  P                  Any other term is matched like a plain query");
+    println!("  Terms next to each other are ANDed. Quote patterns with spaces, parentheses or");
+    println!("  keywords: name:\"my notes*\". Cheap tests on the name run first, then size and");
+    println!("  time checks, and file contents are only read when everything else passed.");
+    println!();
+    println!("CONFIG FILES:");
+    println!("  Defaults are read from ~/.config/filesearch/config.toml (%APPDATA%\\filesearch");
+    println!("  on Windows) and the nearest {} above the current directory.", PROJECT_CONFIG_NAME);
+    println!("  Keys are long option names; [profile.NAME] sections can also set mode,");
+    println!("  query, dirs and output. Command-line flags override config values.");
+    println!();
+    println!("    threads = 8");
+    println!("    case-insensitive = true");
+    println!();
+    println!("    [profile.logs]");
+    println!("    mode = \"file\"");
+    println!("    query = \"*.log\"");
+    println!("    dirs = [\"/var/log\"]");
+    println!();
+    println!("EXIT CODES:");
+    println!("  {}  At least one match was found", EXIT_FOUND);
+    println!("  {}  Nothing matched", EXIT_NOT_FOUND);
+    println!("  {}  Invalid arguments", EXIT_USAGE);
+    println!("  {}  Some directories or entries could not be read", EXIT_IO_ERRORS);
+    println!("  {}  The search timed out or was cancelled", EXIT_INCOMPLETE);
+}
+
+/// Reads a list of paths from a file, or stdin for `-`. Entries are
+/// NUL-separated if the input contains a NUL byte (find -print0,
+/// git ls-files -z), otherwise one per line.
+pub fn read_path_list(source: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut bytes = Vec::new();
+    if source == Path::new("-") {
+        io::Read::read_to_end(&mut io::stdin(), &mut bytes)?;
+    } else {
+        bytes = fs::read(source)?;
+    }
+    
+    let separator = if bytes.contains(&0) { b'\0' } else { b'\n' };
+    
+    let paths = bytes.split(|&b| b == separator)
+        .map(|entry| if separator == b'\n' { entry.strip_suffix(b"\r").unwrap_or(entry) } else { entry })
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            #[cfg(unix)]
+            {
+                use std::os::unix::ffi::OsStrExt;
+                PathBuf::from(std::ffi::OsStr::from_bytes(entry))
+            }
+            
+            #[cfg(not(unix))]
+            {
+                PathBuf::from(String::from_utf8_lossy(entry).into_owned())
+            }
+        })
+        .collect();
+    
+    Ok(paths)
+}
+
+/// Drops start directories that are the same as, or inside, another one, so
+/// nothing is walked twice. Paths are compared after resolving symlinks and
+/// `..`, but the spelling the user gave is what gets searched. With
+/// `--depth`, a nested root reaches further below itself than the outer
+/// root's walk does, so only duplicates are dropped.
+pub fn dedupe_roots(config: &Options, roots: Vec<PathBuf>) -> Vec<PathBuf> {
+    let resolved: Vec<PathBuf> = roots.iter()
+        .map(|root| fs::canonicalize(root).unwrap_or_else(|_| root.clone()))
+        .collect();
+    
+    let mut kept = Vec::new();
+    
+    for (index, root) in roots.iter().enumerate() {
+        let covered_by = (0..roots.len()).find(|&other| {
+            other != index
+                && resolved[index].starts_with(&resolved[other])
+                && (config.max_depth.is_none() || resolved[index] == resolved[other])
+                // Of two identical roots, keep the first one given
+                && (resolved[index] != resolved[other] || other < index)
+        });
+        
+        match covered_by {
+            Some(other) => {
+                if config.is_verbose() {
+                    println!("Skipping {}: already covered by {}", root.display(), roots[other].display());
+                }
+            }
+            None => kept.push(root.clone()),
+        }
+    }
+    
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // Config files on the machine running the tests mustn't leak in
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        let args: Vec<String> = std::iter::once("--no-config")
+            .chain(args.iter().copied())
+            .map(str::to_string)
+            .collect();
+        parse_arguments(&args)
+    }
+    
+    fn error(args: &[&str]) -> String {
+        parse(args).err().expect("the arguments were accepted")
+    }
+    
+    #[test]
+    fn slash_options_pick_the_mode_in_any_case() {
+        assert_eq!(parse(&["/FM", "a", "."]).unwrap().mode, SearchMode::File);
+        assert_eq!(parse(&["/sdm", "a", "."]).unwrap().mode, SearchMode::Directory);
+        assert_eq!(parse(&["/Both", "a", "."]).unwrap().mode, SearchMode::Both);
+    }
+    
+    #[test]
+    fn slash_options_conflict_with_other_modes() {
+        assert!(parse(&["/FM", "/SDM", "a"]).is_err());
+        assert!(parse(&["/FM", "-d", "a"]).is_err());
+        assert!(parse(&["/FM", "-f", "a"]).is_ok());
+    }
+    
+    #[test]
+    fn types_the_mode_rules_out_are_errors() {
+        assert_eq!(error(&["-d", "--type", "l", "a"]), "--type l can't be used with /SDM (-d), which finds only directories");
+        assert_eq!(error(&["/FM", "--type", "f,d", "a"]), "--type d can't be used with /FM (-f), which finds everything but directories");
+        assert!(error(&["-d", "--type", "f,p", "a"]).starts_with("--type f,p "));
+        assert!(error(&["-d", "--executable", "a"]).starts_with("--executable can't be used"));
+        
+        assert_eq!(parse(&["-f", "--type", "f,l,p", "a", "."]).unwrap().file_kinds.len(), 3);
+        assert!(parse(&["-d", "--type", "d", "--empty", "a", "."]).is_ok());
+        assert!(parse(&["-b", "--type", "d,l", "--executable", "a", "."]).is_ok());
+    }
+    
+    #[test]
+    fn other_slash_arguments_are_positionals() {
+        let options = parse(&["/FM", "a", "/tmp"]).unwrap();
+        assert_eq!(options.query, "a");
+        assert_eq!(options.start_dirs, vec![PathBuf::from("/tmp")]);
+    }
+    
+    #[test]
+    fn short_flags_combine() {
+        let options = parse(&["-fi", "a", "."]).unwrap();
+        assert_eq!(options.mode, SearchMode::File);
+        assert!(!options.case_sensitive);
+    }
+    
+    #[test]
+    fn combined_short_flags_take_the_rest_as_a_value() {
+        let options = parse(&["-fit4", "a", "."]).unwrap();
+        assert_eq!(options.max_threads, 4);
+        assert!(!options.case_sensitive);
+        
+        let options = parse(&["-ft", "3", "a", "."]).unwrap();
+        assert_eq!(options.max_threads, 3);
+        assert_eq!(options.query, "a");
+    }
+    
+    #[test]
+    fn missing_values_are_errors() {
+        assert!(error(&["-f", "a", "-t"]).contains("requires a value"));
+        assert!(error(&["-f", "a", "--depth"]).contains("requires a value"));
+        assert!(error(&["-f", "a", "--dfs=1"]).contains("doesn't take a value"));
+    }
+    
+    #[test]
+    fn long_options_take_inline_values() {
+        assert_eq!(parse(&["-f", "a", "--depth=2"]).unwrap().max_depth, Some(2));
+        assert_eq!(parse(&["-f", "a", "--depth", "2"]).unwrap().max_depth, Some(2));
+    }
+    
+    #[test]
+    fn nw_turns_wildcards_off() {
+        assert!(parse(&["-f", "a*"]).unwrap().use_wildcards);
+        assert!(!parse(&["-f", "a*", "-nw"]).unwrap().use_wildcards);
+        assert!(!parse(&["-f", "a*", "--no-wildcards"]).unwrap().use_wildcards);
+    }
+    
+    #[test]
+    fn unknown_options_are_errors() {
+        assert_eq!(error(&["-f", "a", "-Q"]), "Unknown option: -Q");
+        assert_eq!(error(&["-f", "a", "--nope"]), "Unknown option: --nope");
+    }
+    
+    #[test]
+    fn verbose_needs_version() {
+        assert_eq!(error(&["-f", "a", "-v"]), "--verbose only applies to --version");
+        assert_eq!(error(&["-fv", "a"]), "--verbose only applies to --version");
+    }
+    
+    #[test]
+    fn double_dash_ends_options() {
+        let options = parse(&["-f", "--", "-i", "."]).unwrap();
+        assert_eq!(options.query, "-i");
+        assert!(options.case_sensitive);
+    }
+    
+    #[test]
+    fn nested_roots_are_dropped() {
+        let options = parse(&["-f", "a"]).unwrap();
+        let roots = vec![PathBuf::from("a"), PathBuf::from("a/b/c"), PathBuf::from("a"), PathBuf::from("d")];
+        assert_eq!(dedupe_roots(&options, roots), vec![PathBuf::from("a"), PathBuf::from("d")]);
+    }
+    
+    #[test]
+    fn nested_roots_are_kept_with_a_depth_limit() {
+        let options = parse(&["-f", "a", "--depth", "1"]).unwrap();
+        let roots = vec![PathBuf::from("a"), PathBuf::from("a/b/c"), PathBuf::from("a")];
+        assert_eq!(dedupe_roots(&options, roots), vec![PathBuf::from("a"), PathBuf::from("a/b/c")]);
+    }
+    
+    #[test]
+    fn durations_take_units() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+    }
+    
+    #[test]
+    fn bad_durations_are_errors() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("5d").unwrap_err().contains("unit"));
+        assert!(parse_duration("1..2s").is_err());
+    }
+}
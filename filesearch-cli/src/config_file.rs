@@ -0,0 +1,423 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use filesearch_core::SearchMode;
+
+use crate::args::{apply_option, reset_option, Options, OutputMode, OPTIONS};
+
+// Defaults are read from the user config, then from the nearest
+// .filesearch.toml, then the chosen profile; the command line goes last.
+// Keys are the long option names, e.g.
+//
+//     threads = 8
+//     case-insensitive = true
+//
+//     [profile.logs]
+//     mode = "file"
+//     query = "*.log"
+//     dirs = ["/var/log"]
+//     output = "count"
+
+pub const PROJECT_CONFIG_NAME: &str = ".filesearch.toml";
+
+#[derive(Clone, Debug)]
+enum ConfigValue {
+    Text(String),
+    Integer(i64),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+struct ConfigEntry {
+    profile: Option<String>,
+    key: String,
+    value: ConfigValue,
+    line: usize,
+}
+
+struct ConfigFile {
+    path: PathBuf,
+    entries: Vec<ConfigEntry>,
+}
+
+/// Parts of a search a config file can set that aren't options.
+#[derive(Default)]
+pub struct Preset {
+    pub mode: Option<SearchMode>,
+    pub query: Option<String>,
+    pub dirs: Vec<PathBuf>,
+}
+
+fn user_config_path() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        env::var("APPDATA").ok().map(|appdata| PathBuf::from(appdata).join("filesearch").join("config.toml"))
+    }
+    
+    #[cfg(not(target_os = "windows"))]
+    {
+        let config_home = match env::var("XDG_CONFIG_HOME") {
+            Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var("HOME").ok()?).join(".config"),
+        };
+        Some(config_home.join("filesearch").join("config.toml"))
+    }
+}
+
+/// The nearest .filesearch.toml in the current directory or one of its parents.
+fn project_config_path() -> Option<PathBuf> {
+    let current_dir = env::current_dir().ok()?;
+    current_dir.ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_NAME))
+        .find(|path| path.is_file())
+}
+
+pub fn load_config_files(profile: Option<&str>, config: &mut Options, preset: &mut Preset) -> Result<(), String> {
+    let mut files = Vec::new();
+    for path in [user_config_path(), project_config_path()].into_iter().flatten() {
+        // The project file may be the user file when run from ~/.config/filesearch
+        if path.is_file() && !files.iter().any(|file: &ConfigFile| file.path == path) {
+            files.push(ConfigFile::load(&path)?);
+        }
+    }
+    
+    for file in &files {
+        file.apply(None, config, preset)?;
+    }
+    
+    if let Some(profile) = profile {
+        if !files.iter().any(|file| file.has_profile(profile)) {
+            return Err(format!("Unknown profile: {}", profile));
+        }
+        for file in &files {
+            file.apply(Some(profile), config, preset)?;
+        }
+    }
+    
+    Ok(())
+}
+
+impl ConfigFile {
+    fn load(path: &Path) -> Result<ConfigFile, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read config file '{}': {}", path.display(), e))?;
+        let error = |line: usize, message: String| format!("{}:{}: {}", path.display(), line, message);
+        
+        let mut entries = Vec::new();
+        let mut profile = None;
+        
+        for (index, raw_line) in contents.lines().enumerate() {
+            let line_no = index + 1;
+            let line = strip_comment(raw_line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            
+            if let Some(section) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+                let name = section.trim().strip_prefix("profile.")
+                    .ok_or_else(|| error(line_no, format!("unknown section [{}]", section)))?;
+                profile = Some(name.trim().trim_matches('"').to_string());
+                continue;
+            }
+            
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| error(line_no, "expected key = value".to_string()))?;
+            let key = key.trim();
+            if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                return Err(error(line_no, format!("invalid key '{}'", key)));
+            }
+            
+            entries.push(ConfigEntry {
+                profile: profile.clone(),
+                key: key.replace('_', "-"),
+                value: parse_config_value(value.trim()).map_err(|e| error(line_no, e))?,
+                line: line_no,
+            });
+        }
+        
+        Ok(ConfigFile { path: path.to_path_buf(), entries })
+    }
+    
+    fn has_profile(&self, name: &str) -> bool {
+        self.entries.iter().any(|entry| entry.profile.as_deref() == Some(name))
+    }
+    
+    /// Applies the top-level defaults, or the entries of one profile.
+    fn apply(&self, profile: Option<&str>, config: &mut Options, preset: &mut Preset) -> Result<(), String> {
+        for entry in self.entries.iter().filter(|entry| entry.profile.as_deref() == profile) {
+            apply_config_entry(entry, config, preset)
+                .map_err(|e| format!("{}:{}: {}", self.path.display(), entry.line, e))?;
+        }
+        Ok(())
+    }
+}
+
+fn apply_config_entry(entry: &ConfigEntry, config: &mut Options, preset: &mut Preset) -> Result<(), String> {
+    let text = match entry.value {
+        ConfigValue::Text(ref text) => Some(text.clone()),
+        ConfigValue::Integer(number) => Some(number.to_string()),
+        _ => None,
+    };
+    let expect_text = || text.clone().ok_or_else(|| format!("'{}' expects a string", entry.key));
+    
+    match entry.key.as_str() {
+        "mode" => {
+            preset.mode = Some(match expect_text()?.as_str() {
+                "file" => SearchMode::File,
+                "dir" | "directory" => SearchMode::Directory,
+                "both" => SearchMode::Both,
+                other => return Err(format!("unknown mode '{}' (use file, dir or both)", other)),
+            });
+        }
+        "query" => preset.query = Some(expect_text()?),
+        "dirs" => {
+            preset.dirs = match entry.value {
+                ConfigValue::List(ref dirs) => dirs.iter().map(PathBuf::from).collect(),
+                _ => vec![PathBuf::from(expect_text()?)],
+            };
+        }
+        "output" => {
+            config.output = match expect_text()?.as_str() {
+                "normal" => OutputMode::Normal,
+                "quiet" => OutputMode::Quiet,
+                "count" => OutputMode::Count,
+                "print0" => OutputMode::Print0,
+                "tui" => OutputMode::Tui,
+                "json" => OutputMode::Json,
+                "csv" => OutputMode::Csv,
+                other => return Err(format!("unknown output '{}' (use normal, quiet, count, print0, tui, json or csv)", other)),
+            };
+        }
+        key => {
+            let spec = OPTIONS.iter()
+                .find(|spec| spec.long == key)
+                .filter(|spec| !matches!(spec.long, "file" | "dir" | "both" | "help" | "version" | "verbose" | "resume" | "profile" | "no-config"))
+                .ok_or_else(|| format!("unknown setting '{}'", key))?;
+            
+            if spec.takes_value {
+                apply_option(config, key, Some(&expect_text()?))?;
+            } else {
+                match entry.value {
+                    ConfigValue::Bool(true) => apply_option(config, key, None)?,
+                    ConfigValue::Bool(false) => reset_option(config, key),
+                    _ => return Err(format!("'{}' expects true or false", key)),
+                }
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Cuts a `#` comment off a line, leaving `#` inside strings alone.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    
+    for (index, c) in line.char_indices() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' => return &line[..index],
+            None => {}
+        }
+    }
+    
+    line
+}
+
+// Only the parts of TOML a settings file needs: strings, whole numbers,
+// booleans and single-line arrays of strings
+fn parse_config_value(text: &str) -> Result<ConfigValue, String> {
+    match text {
+        "true" => return Ok(ConfigValue::Bool(true)),
+        "false" => return Ok(ConfigValue::Bool(false)),
+        _ => {}
+    }
+    
+    if let Some(mut rest) = text.strip_prefix('[') {
+        let mut items = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            if let Some(after) = rest.strip_prefix(']') {
+                if !after.trim().is_empty() {
+                    return Err("unexpected text after array".to_string());
+                }
+                return Ok(ConfigValue::List(items));
+            }
+            
+            let (item, after) = parse_config_string(rest)?;
+            items.push(item);
+            rest = after.trim_start();
+            if let Some(after) = rest.strip_prefix(',') {
+                rest = after;
+            } else if !rest.starts_with(']') {
+                return Err("expected ',' or ']' in array".to_string());
+            }
+        }
+    }
+    
+    if text.starts_with('"') || text.starts_with('\'') {
+        let (value, rest) = parse_config_string(text)?;
+        if !rest.trim().is_empty() {
+            return Err("unexpected text after string".to_string());
+        }
+        return Ok(ConfigValue::Text(value));
+    }
+    
+    text.replace('_', "").parse()
+        .map(ConfigValue::Integer)
+        .map_err(|_| format!("invalid value '{}' (strings need quotes)", text))
+}
+
+/// Parses a quoted string at the start of `text`, returning it and the rest.
+fn parse_config_string(text: &str) -> Result<(String, &str), String> {
+    let mut chars = text.char_indices();
+    let quote = match chars.next() {
+        Some((_, c)) if c == '"' || c == '\'' => c,
+        _ => return Err(format!("expected a quoted string, found '{}'", text)),
+    };
+    
+    let mut value = String::new();
+    while let Some((index, c)) = chars.next() {
+        if c == quote {
+            return Ok((value, &text[index + 1..]));
+        }
+        // Single-quoted strings are literal, like TOML
+        if c != '\\' || quote == '\'' {
+            value.push(c);
+            continue;
+        }
+        
+        match chars.next() {
+            Some((_, '\\')) => value.push('\\'),
+            Some((_, '"')) => value.push('"'),
+            Some((_, 'n')) => value.push('\n'),
+            Some((_, 't')) => value.push('\t'),
+            Some((_, other)) => return Err(format!("unknown escape '\\{}'", other)),
+            None => break,
+        }
+    }
+    
+    Err("unterminated string".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::parse_arguments;
+    
+    fn text(value: Result<ConfigValue, String>) -> String {
+        match value {
+            Ok(ConfigValue::Text(text)) => text,
+            _ => panic!("not a string"),
+        }
+    }
+    
+    fn write_config(name: &str, contents: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("filesearch-{}-{}.toml", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+    
+    fn options() -> Options {
+        let args = ["--no-config", "-f", "query"].map(String::from);
+        parse_arguments(&args).unwrap_or_else(|e| panic!("{}", e))
+    }
+    
+    #[test]
+    fn values_parse() {
+        assert!(matches!(parse_config_value("true"), Ok(ConfigValue::Bool(true))));
+        assert!(matches!(parse_config_value("false"), Ok(ConfigValue::Bool(false))));
+        assert!(matches!(parse_config_value("1_000"), Ok(ConfigValue::Integer(1000))));
+        assert!(matches!(parse_config_value("-3"), Ok(ConfigValue::Integer(-3))));
+        assert_eq!(text(parse_config_value("\"*.log\"")), "*.log");
+        assert!(parse_config_value("bare").unwrap_err().contains("strings need quotes"));
+    }
+    
+    #[test]
+    fn strings_unescape_unless_single_quoted() {
+        assert_eq!(text(parse_config_value(r#""a\tb\\c\"d""#)), "a\tb\\c\"d");
+        assert_eq!(text(parse_config_value(r"'C:\logs\n'")), r"C:\logs\n");
+        assert!(parse_config_value(r#""\q""#).unwrap_err().contains("unknown escape"));
+        assert_eq!(parse_config_value("\"open").unwrap_err(), "unterminated string");
+        assert!(parse_config_value("\"a\" b").is_err());
+    }
+    
+    #[test]
+    fn arrays_hold_strings() {
+        match parse_config_value(r#"[ "/var/log", '/tmp' ,]"#) {
+            Ok(ConfigValue::List(items)) => assert_eq!(items, ["/var/log", "/tmp"]),
+            _ => panic!("not a list"),
+        }
+        assert!(matches!(parse_config_value("[]"), Ok(ConfigValue::List(items)) if items.is_empty()));
+        assert!(parse_config_value(r#"["a" "b"]"#).is_err());
+        assert!(parse_config_value(r#"["a"] x"#).is_err());
+        assert!(parse_config_value("[1]").is_err());
+    }
+    
+    #[test]
+    fn comments_stop_outside_strings() {
+        assert_eq!(strip_comment("threads = 4 # cores"), "threads = 4 ");
+        assert_eq!(strip_comment(r##"query = "#1" # issue"##), r##"query = "#1" "##);
+        assert_eq!(strip_comment(r##"query = "a\"#" # x"##), r##"query = "a\"#" "##);
+        assert_eq!(strip_comment("query = '#' # x"), "query = '#' ");
+    }
+    
+    #[test]
+    fn files_apply_defaults_then_profiles() {
+        let path = write_config("profiles", "\
+threads = 3
+case_insensitive = true
+
+[profile.logs]
+mode = \"file\"
+query = \"*.log\"
+dirs = [\"/var/log\"]
+depth = 2
+case-insensitive = false
+");
+        let file = ConfigFile::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(file.has_profile("logs"));
+        assert!(!file.has_profile("other"));
+        
+        let mut config = options();
+        let mut preset = Preset::default();
+        file.apply(None, &mut config, &mut preset).unwrap();
+        assert_eq!(config.max_threads, 3);
+        assert!(!config.case_sensitive);
+        assert!(preset.query.is_none());
+        
+        file.apply(Some("logs"), &mut config, &mut preset).unwrap();
+        assert_eq!(preset.mode, Some(SearchMode::File));
+        assert_eq!(preset.query.as_deref(), Some("*.log"));
+        assert_eq!(preset.dirs, [PathBuf::from("/var/log")]);
+        assert_eq!(config.max_depth, Some(2));
+        assert!(config.case_sensitive);
+    }
+    
+    #[test]
+    fn errors_name_the_line() {
+        let path = write_config("errors", "threads = 2\n\nnonsense\n");
+        let error = ConfigFile::load(&path).err().unwrap();
+        assert_eq!(error, format!("{}:3: expected key = value", path.display()));
+        
+        fs::write(&path, "[other]\n").unwrap();
+        assert!(ConfigFile::load(&path).err().unwrap().ends_with(":1: unknown section [other]"));
+        
+        fs::write(&path, "\n# settings\nthreads = 2\nverbose = true\n").unwrap();
+        let file = ConfigFile::load(&path).unwrap();
+        let error = file.apply(None, &mut options(), &mut Preset::default()).unwrap_err();
+        assert!(error.ends_with(":4: unknown setting 'verbose'"), "{}", error);
+        
+        fs::write(&path, "threads = \"lots\"\n").unwrap();
+        let file = ConfigFile::load(&path).unwrap();
+        assert!(file.apply(None, &mut options(), &mut Preset::default()).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,455 @@
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use filesearch_core::{decode_path, encode_path, escape_text, unescape_text, StopReason};
+
+use crate::args::Options;
+use crate::{EXIT_FOUND, EXIT_NOT_FOUND, EXIT_USAGE};
+
+// Every search is appended to a history file as a block of `key: value`
+// lines, the same details setup_logging puts in a log's session header.
+// `save` copies a record into a second file under a name.
+
+const HISTORY_LIMIT: usize = 500;
+const HISTORY_SHOWN: usize = 20;
+
+struct HistoryRecord {
+    // The id for history entries, the name for saved searches
+    label: String,
+    time: u64,
+    cwd: PathBuf,
+    args: Vec<String>,
+    query: String,
+    dirs: Vec<PathBuf>,
+    matches: usize,
+    status: String,
+}
+
+fn history_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        env::var("LOCALAPPDATA").ok().map(|dir| PathBuf::from(dir).join("filesearch"))
+    }
+    
+    #[cfg(not(target_os = "windows"))]
+    {
+        let data_home = match env::var("XDG_DATA_HOME") {
+            Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var("HOME").ok()?).join(".local").join("share"),
+        };
+        Some(data_home.join("filesearch"))
+    }
+}
+
+fn history_file(name: &str) -> io::Result<PathBuf> {
+    history_dir()
+        .map(|dir| dir.join(name))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory to keep the history in"))
+}
+
+impl HistoryRecord {
+    fn write(&self, file: &mut impl Write, kind: &str) -> io::Result<()> {
+        writeln!(file, "[{} {}]", kind, self.label)?;
+        writeln!(file, "time: {}", self.time)?;
+        writeln!(file, "cwd: {}", encode_path(&self.cwd))?;
+        for arg in &self.args {
+            writeln!(file, "arg: {}", escape_text(arg.as_bytes()))?;
+        }
+        writeln!(file, "query: {}", escape_text(self.query.as_bytes()))?;
+        for dir in &self.dirs {
+            writeln!(file, "dir: {}", encode_path(dir))?;
+        }
+        writeln!(file, "matches: {}", self.matches)?;
+        writeln!(file, "status: {}", self.status)
+    }
+    
+    /// Reads every record in a history or saved-searches file. A missing
+    /// file is just an empty history.
+    fn read_all(path: &Path) -> Result<Vec<HistoryRecord>, String> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("Cannot read '{}': {}", path.display(), e)),
+        };
+        let bad_line = |line: usize| format!("{}:{}: invalid history entry", path.display(), line);
+        let text = |value: &str, line: usize| {
+            unescape_text(value)
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .ok_or_else(|| bad_line(line))
+        };
+        
+        let mut records: Vec<HistoryRecord> = Vec::new();
+        
+        for (index, line) in contents.lines().enumerate() {
+            let line_no = index + 1;
+            
+            if let Some(header) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+                let (_, label) = header.split_once(' ').ok_or_else(|| bad_line(line_no))?;
+                records.push(HistoryRecord {
+                    label: label.to_string(),
+                    time: 0,
+                    cwd: PathBuf::new(),
+                    args: Vec::new(),
+                    query: String::new(),
+                    dirs: Vec::new(),
+                    matches: 0,
+                    status: String::new(),
+                });
+                continue;
+            }
+            
+            let record = records.last_mut().ok_or_else(|| bad_line(line_no))?;
+            let (key, value) = line.split_once(": ").ok_or_else(|| bad_line(line_no))?;
+            match key {
+                "time" => record.time = value.parse().map_err(|_| bad_line(line_no))?,
+                "cwd" => record.cwd = decode_path(value).ok_or_else(|| bad_line(line_no))?,
+                "arg" => record.args.push(text(value, line_no)?),
+                "query" => record.query = text(value, line_no)?,
+                "dir" => record.dirs.push(decode_path(value).ok_or_else(|| bad_line(line_no))?),
+                "matches" => record.matches = value.parse().map_err(|_| bad_line(line_no))?,
+                "status" => record.status = value.to_string(),
+                // Newer versions may record more; skip what we don't know
+                _ => {}
+            }
+        }
+        
+        Ok(records)
+    }
+    
+    fn write_all(path: &Path, records: &[HistoryRecord], kind: &str) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+        
+        let mut file = io::BufWriter::new(fs::File::create(&tmp_path)?);
+        for record in records {
+            record.write(&mut file, kind)?;
+        }
+        file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        fs::rename(&tmp_path, path)
+    }
+    
+    fn print(&self, tag: &str) {
+        let dirs: Vec<String> = self.dirs.iter().map(|dir| dir.display().to_string()).collect();
+        let status = if self.status == "completed" { String::new() } else { format!(" ({})", self.status) };
+        
+        println!("{:<6} {}  \"{}\" in {}  {} matches{}",
+                 tag, format_timestamp(self.time), self.query, dirs.join(", "), self.matches, status);
+        println!("{:<6} filesearch {}", "", self.args.iter().map(|arg| quote_arg(arg)).collect::<Vec<_>>().join(" "));
+    }
+}
+
+pub fn record_search(args: &[String], config: &Options, matches: usize, stop_reason: StopReason) -> io::Result<()> {
+    let path = history_file("history")?;
+    let mut records = HistoryRecord::read_all(&path)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    
+    let next_id = records.iter()
+        .filter_map(|record| record.label.parse::<u64>().ok())
+        .max()
+        .unwrap_or(0) + 1;
+    
+    records.push(HistoryRecord {
+        label: next_id.to_string(),
+        time: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        cwd: env::current_dir().unwrap_or_default(),
+        args: args.to_vec(),
+        query: config.query.clone(),
+        dirs: config.start_dirs.clone(),
+        matches,
+        status: match stop_reason {
+            StopReason::Completed => "completed",
+            StopReason::Cancelled => "cancelled",
+            StopReason::TimedOut => "timed out",
+            StopReason::Stopped => "stopped",
+        }.to_string(),
+    });
+    
+    let keep_from = records.len().saturating_sub(HISTORY_LIMIT);
+    HistoryRecord::write_all(&path, &records[keep_from..], "search")
+}
+
+pub fn history_command(args: &[String]) -> i32 {
+    let shown = match args {
+        [] => HISTORY_SHOWN,
+        [count] => match count.parse() {
+            Ok(count) => count,
+            Err(_) => {
+                eprintln!("Error: Invalid count '{}' for history: expected a whole number", count);
+                return EXIT_USAGE;
+            }
+        },
+        _ => {
+            eprintln!("Error: Usage: filesearch history [N]");
+            return EXIT_USAGE;
+        }
+    };
+    
+    let load = |name: &str| history_file(name)
+        .map_err(|e| e.to_string())
+        .and_then(|path| HistoryRecord::read_all(&path));
+    let (history, saved) = match (load("history"), load("saved")) {
+        (Ok(history), Ok(saved)) => (history, saved),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("Error: {}", e);
+            return EXIT_USAGE;
+        }
+    };
+    
+    if history.is_empty() && saved.is_empty() {
+        println!("No searches recorded yet.");
+        return EXIT_NOT_FOUND;
+    }
+    
+    if !saved.is_empty() {
+        println!("Saved searches:");
+        for record in &saved {
+            record.print(&record.label);
+        }
+        println!();
+    }
+    
+    if !history.is_empty() {
+        println!("Recent searches:");
+        for record in &history[history.len().saturating_sub(shown)..] {
+            record.print(&format!("#{}", record.label));
+        }
+    }
+    
+    EXIT_FOUND
+}
+
+/// `save NAME [ID]`: bookmarks a history entry, the latest by default.
+pub fn save_command(args: &[String]) -> i32 {
+    let (name, id) = match args {
+        [name] => (name, None),
+        [name, id] => (name, Some(id.trim_start_matches('#'))),
+        _ => {
+            eprintln!("Error: Usage: filesearch save [name] [id]");
+            return EXIT_USAGE;
+        }
+    };
+    
+    // Names that look like ids would make `rerun` ambiguous
+    if name.is_empty() || name.parse::<u64>().is_ok() || name.chars().any(|c| c.is_whitespace() || c == ']') {
+        eprintln!("Error: Invalid name '{}': use letters, digits and punctuation, not just a number", name);
+        return EXIT_USAGE;
+    }
+    
+    let result = history_file("history").and_then(|history_path| {
+        let saved_path = history_file("saved")?;
+        let to_io = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+        
+        let history = HistoryRecord::read_all(&history_path).map_err(to_io)?;
+        let mut record = match id {
+            Some(id) => history.into_iter().find(|record| record.label == id),
+            None => history.into_iter().last(),
+        }.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such search in the history"))?;
+        record.label = name.clone();
+        
+        let mut saved = HistoryRecord::read_all(&saved_path).map_err(to_io)?;
+        saved.retain(|existing| existing.label != *name);
+        saved.push(record);
+        HistoryRecord::write_all(&saved_path, &saved, "saved")
+    });
+    
+    match result {
+        Ok(()) => {
+            println!("Saved as '{}'. Run it again with: filesearch rerun {}", name, name);
+            EXIT_FOUND
+        }
+        Err(e) => {
+            eprintln!("Error: Cannot save search: {}", e);
+            EXIT_USAGE
+        }
+    }
+}
+
+/// `rerun ID|NAME [FLAGS]`: the recorded arguments, with any extra flags
+/// appended so they override the originals. Runs from the directory the
+/// search was first made in, so relative paths mean the same thing.
+pub fn rerun_arguments(args: &[String]) -> Result<Vec<String>, String> {
+    let dir = history_dir().ok_or("no home directory to keep the history in")?;
+    let (cwd, rerun_args) = recorded_arguments(&dir, args)?;
+    
+    if cwd.is_dir() {
+        env::set_current_dir(&cwd).map_err(|e| e.to_string())?;
+    }
+    Ok(rerun_args)
+}
+
+// The directory a recorded search ran in and its arguments, extra flags included
+fn recorded_arguments(history_dir: &Path, args: &[String]) -> Result<(PathBuf, Vec<String>), String> {
+    let (target, extra) = args.split_first()
+        .ok_or("Usage: filesearch rerun [id|name] [flags]")?;
+    let target = target.trim_start_matches('#');
+    
+    let file = if target.parse::<u64>().is_ok() { "history" } else { "saved" };
+    let record = HistoryRecord::read_all(&history_dir.join(file))?
+        .into_iter()
+        .find(|record| record.label == target)
+        .ok_or_else(|| format!("No search '{}' in the history", target))?;
+    
+    let mut rerun_args = record.args;
+    rerun_args.extend(extra.iter().cloned());
+    Ok((record.cwd, rerun_args))
+}
+
+/// Formats seconds since the Unix epoch as a UTC date and time.
+fn format_timestamp(secs: u64) -> String {
+    // Civil-from-days conversion (Howard Hinnant's algorithm)
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    
+    format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", year, month, day, (secs / 3600) % 24, (secs / 60) % 60)
+}
+
+/// Quotes an argument for display if a shell would split or expand it.
+fn quote_arg(arg: &str) -> String {
+    let plain = !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn record(label: &str, args: &[&str]) -> HistoryRecord {
+        HistoryRecord {
+            label: label.to_string(),
+            time: 1_700_000_000,
+            cwd: PathBuf::from("/srv/project"),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            query: args[1].to_string(),
+            dirs: vec![PathBuf::from("src")],
+            matches: 3,
+            status: "completed".to_string(),
+        }
+    }
+    
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+    
+    // A history and a saved-searches file in a directory of their own
+    fn history_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("filesearch-history-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        HistoryRecord::write_all(&dir.join("history"), &[
+            record("1", &["-f", "*.rs", "src"]),
+            record("2", &["-d", "target", "."]),
+        ], "search").unwrap();
+        HistoryRecord::write_all(&dir.join("saved"), &[record("logs", &["-f", "*.log", "/var/log"])], "saved").unwrap();
+        dir
+    }
+    
+    #[test]
+    fn records_round_trip() {
+        let dir = env::temp_dir().join(format!("filesearch-history-round-trip-{}", std::process::id()));
+        let path = dir.join("history");
+        let mut original = record("7", &["-f", "two words\nand a newline\n", "--ext", "rs"]);
+        original.status = "timed out".to_string();
+        HistoryRecord::write_all(&path, &[original], "search").unwrap();
+        
+        let records = HistoryRecord::read_all(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(record.label, "7");
+        assert_eq!(record.time, 1_700_000_000);
+        assert_eq!(record.cwd, PathBuf::from("/srv/project"));
+        assert_eq!(record.args, strings(&["-f", "two words\nand a newline\n", "--ext", "rs"]));
+        assert_eq!(record.query, "two words\nand a newline\n");
+        assert_eq!(record.dirs, [PathBuf::from("src")]);
+        assert_eq!(record.matches, 3);
+        assert_eq!(record.status, "timed out");
+    }
+    
+    #[test]
+    fn missing_history_is_empty() {
+        let path = env::temp_dir().join("filesearch-history-that-does-not-exist");
+        assert!(HistoryRecord::read_all(&path).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn rerun_finds_ids_in_the_history() {
+        let dir = history_dir("ids");
+        let (cwd, args) = recorded_arguments(&dir, &strings(&["2"])).unwrap();
+        assert_eq!(cwd, PathBuf::from("/srv/project"));
+        assert_eq!(args, strings(&["-d", "target", "."]));
+        
+        let (_, args) = recorded_arguments(&dir, &strings(&["#1"])).unwrap();
+        assert_eq!(args, strings(&["-f", "*.rs", "src"]));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn rerun_finds_names_in_saved_searches() {
+        let dir = history_dir("names");
+        let (_, args) = recorded_arguments(&dir, &strings(&["logs"])).unwrap();
+        assert_eq!(args, strings(&["-f", "*.log", "/var/log"]));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn rerun_appends_extra_flags() {
+        let dir = history_dir("extra");
+        let (_, args) = recorded_arguments(&dir, &strings(&["1", "-i", "--depth", "2"])).unwrap();
+        assert_eq!(args, strings(&["-f", "*.rs", "src", "-i", "--depth", "2"]));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn rerun_reports_unknown_searches() {
+        let dir = history_dir("unknown");
+        assert_eq!(recorded_arguments(&dir, &strings(&["9"])).unwrap_err(), "No search '9' in the history");
+        assert_eq!(recorded_arguments(&dir, &strings(&["other"])).unwrap_err(), "No search 'other' in the history");
+        assert!(recorded_arguments(&dir, &[]).unwrap_err().starts_with("Usage:"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn broken_history_names_the_line() {
+        let dir = history_dir("broken");
+        let path = dir.join("history");
+        fs::write(&path, "[search 1]\ntime: soon\n").unwrap();
+        let error = recorded_arguments(&dir, &strings(&["1"])).unwrap_err();
+        assert_eq!(error, format!("{}:2: invalid history entry", path.display()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn timestamps_are_utc() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00 UTC");
+        assert_eq!(format_timestamp(951_825_600), "2000-02-29 12:00 UTC");
+    }
+    
+    #[test]
+    fn arguments_are_quoted_for_display() {
+        assert_eq!(quote_arg("--depth=2"), "--depth=2");
+        assert_eq!(quote_arg("*.rs"), "'*.rs'");
+        assert_eq!(quote_arg("it's"), "'it'\\''s'");
+        assert_eq!(quote_arg(""), "''");
+    }
+}
@@ -0,0 +1,105 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+// The handler only flips the engine's cancellation flag, so the workers
+// can flush what they have found and the usual summary still gets written.
+// A second Ctrl-C while the search is winding down quits immediately.
+static INTERRUPT_FLAG: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+/// Returns true if this was the first interrupt of the search.
+fn on_interrupt() -> bool {
+    match INTERRUPT_FLAG.get() {
+        Some(flag) => !flag.swap(true, Ordering::SeqCst),
+        None => false,
+    }
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    
+    pub const SUPPORTED: bool = true;
+    
+    const SIGINT: i32 = 2;
+    const SIG_DFL: usize = 0;
+    
+    extern "C" {
+        fn signal(signum: i32, handler: usize) -> usize;
+        fn _exit(status: i32) -> !;
+    }
+    
+    extern "C" fn handle_sigint(_signum: i32) {
+        if !super::on_interrupt() {
+            // Only async-signal-safe calls are allowed in here
+            unsafe { _exit(130) }
+        }
+    }
+    
+    pub fn install(flag: Arc<AtomicBool>) {
+        let _ = super::INTERRUPT_FLAG.set(flag);
+        unsafe {
+            signal(SIGINT, handle_sigint as extern "C" fn(i32) as usize);
+        }
+    }
+    
+    pub fn restore() {
+        unsafe {
+            signal(SIGINT, SIG_DFL);
+        }
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    
+    pub const SUPPORTED: bool = true;
+    
+    const CTRL_C_EVENT: u32 = 0;
+    const CTRL_BREAK_EVENT: u32 = 1;
+    
+    type HandlerRoutine = extern "system" fn(u32) -> i32;
+    
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetConsoleCtrlHandler(handler: Option<HandlerRoutine>, add: i32) -> i32;
+    }
+    
+    extern "system" fn handle_ctrl(ctrl_type: u32) -> i32 {
+        if ctrl_type != CTRL_C_EVENT && ctrl_type != CTRL_BREAK_EVENT {
+            return 0;
+        }
+        // Returning FALSE on the second press lets the default handler
+        // terminate the process
+        if super::on_interrupt() { 1 } else { 0 }
+    }
+    
+    pub fn install(flag: Arc<AtomicBool>) {
+        let _ = super::INTERRUPT_FLAG.set(flag);
+        unsafe {
+            SetConsoleCtrlHandler(Some(handle_ctrl), 1);
+        }
+    }
+    
+    pub fn restore() {
+        unsafe {
+            SetConsoleCtrlHandler(Some(handle_ctrl), 0);
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod imp {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    
+    pub const SUPPORTED: bool = false;
+    
+    pub fn install(_flag: Arc<AtomicBool>) {}
+    
+    pub fn restore() {}
+}
+
+pub use imp::{install, restore, SUPPORTED};
@@ -0,0 +1,604 @@
+//The original program was written in C with help from ChatGPT 3-turbo. 
+//This RUst version was the Upgrade of the SDM Version, both converted (the direct file was one and done) using Deepseek AI. 
+//This version specifically was longer to perfect, but it works. This will be the underlying version for the GUI Version.
+mod args;
+mod config_file;
+mod history;
+mod interrupt;
+mod tui;
+
+use std::borrow::Cow;
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use filesearch_core::{
+    detect_mime, display_path, system_description, Checkpoint, FuzzyPattern, Match, SearchEngine, SearchError,
+    SearchErrorKind, SearchEvent, SearchStats, StopReason,
+};
+
+use args::{dedupe_roots, parse_arguments, read_path_list, Options, OutputMode, SavePolicy};
+use history::{history_command, record_search, rerun_arguments, save_command};
+
+// Exit codes, so scripts can tell what happened without parsing output
+pub const EXIT_FOUND: i32 = 0;
+pub const EXIT_NOT_FOUND: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_IO_ERRORS: i32 = 3;
+pub const EXIT_INCOMPLETE: i32 = 4;
+
+// ==============================================
+// CONSOLE OUTPUT
+// ==============================================
+
+/// Prints matches, warnings, progress and the summary as the engine
+/// reports them; the console is just one subscriber to the search's events.
+struct ConsoleReporter {
+    verbose: bool,
+    warnings: bool,
+    // Only worth naming the root when there is more than one
+    multiple_roots: bool,
+    // Fuzzy matches are printed at the end, once they can be ranked
+    ranked: bool,
+    // -0: bare paths for another program, nothing else
+    null_separated: bool,
+    // --json and --csv: a record per match, nothing else
+    records: Option<RecordFormat>,
+    timeout: Option<Duration>,
+    checkpoint_path: Option<PathBuf>,
+    checkpoint_failed: bool,
+}
+
+impl ConsoleReporter {
+    fn new(options: &Options) -> Self {
+        Self {
+            verbose: options.is_verbose(),
+            warnings: options.shows_warnings(),
+            multiple_roots: options.start_dirs.len() > 1,
+            ranked: options.fuzzy,
+            null_separated: options.output == OutputMode::Print0,
+            records: RecordFormat::for_output(options.output),
+            timeout: options.timeout,
+            checkpoint_path: options.checkpoint_path.clone(),
+            checkpoint_failed: false,
+        }
+    }
+    
+    fn handle(&mut self, event: &SearchEvent) {
+        if let (SearchEvent::Match(found), Some(format)) = (event, self.records) {
+            if !self.ranked {
+                print_record(format, &found.path);
+            }
+            return;
+        }
+        
+        match event {
+            SearchEvent::Error(error) => self.print_error(error),
+            SearchEvent::Match(found) if self.null_separated && !self.ranked => print_null_terminated(&found.path),
+            _ if !self.verbose => {}
+            SearchEvent::Started { query, start_dirs, threads, listed_paths } => {
+                println!("Starting search with {} thread(s)...", threads);
+                println!("Pattern: {}", query);
+                for start_dir in start_dirs {
+                    println!("Directory: {}", start_dir.display());
+                }
+                if *listed_paths > 0 {
+                    println!("Listed paths: {}", listed_paths);
+                }
+            }
+            SearchEvent::DirEntered { .. } => {}
+            SearchEvent::Match(_) if self.ranked => {}
+            SearchEvent::Match(found) => self.print_match(found),
+            SearchEvent::Progress { dirs, files, matches, rate } => {
+                print!("\rProgress: {} dirs, {} files, {} matches, {:.1} files/sec",
+                    dirs, files, matches, rate);
+                io::stdout().flush().ok();
+            }
+            SearchEvent::Stopping { reason } => match (reason, self.timeout) {
+                (StopReason::TimedOut, Some(timeout)) => {
+                    println!("\nTime limit of {:.1} seconds reached, stopping search...", timeout.as_secs_f32());
+                }
+                // Declined at the page prompt, which already said so
+                (StopReason::Stopped, _) => {}
+                _ => println!("\nInterrupted, finishing up... (press Ctrl-C again to quit immediately)"),
+            },
+            SearchEvent::Finished { stats, reason } => {
+                if let Some(ref checkpoint_path) = self.checkpoint_path {
+                    if !self.checkpoint_failed {
+                        println!("\nCheckpoint saved to: {}", checkpoint_path.display());
+                    }
+                }
+                print_summary(stats, *reason);
+            }
+        }
+    }
+    
+    fn print_match(&self, found: &Match) {
+        let kind = if found.is_dir { "directory" } else { "file" };
+        let root_note = match found.root {
+            Some(ref root) if self.multiple_roots => format!(" [root: {}]", display_path(root)),
+            _ => String::new(),
+        };
+        
+        match found.thread_id {
+            Some(thread_id) => println!("[Thread {}] Found {}: {}{}", thread_id, kind, display_path(&found.path), root_note),
+            None => println!("Found {}: {}{}", kind, display_path(&found.path), root_note),
+        }
+    }
+    
+    fn print_error(&mut self, error: &SearchError) {
+        if error.kind == SearchErrorKind::Checkpoint {
+            self.checkpoint_failed = true;
+        }
+        if !self.warnings {
+            return;
+        }
+        
+        // Skip directories we can't access (common on Windows)
+        if cfg!(windows) && error.kind == SearchErrorKind::ReadDir
+            && matches!(error.error.kind(), io::ErrorKind::PermissionDenied | io::ErrorKind::NotFound)
+        {
+            return;
+        }
+        
+        match (error.kind, error.thread_id) {
+            (SearchErrorKind::Checkpoint, _) => eprintln!("\nWarning: {}", error),
+            (_, Some(thread_id)) => eprintln!("[Thread {}] Warning: {}", thread_id, error),
+            (_, None) => eprintln!("Warning: {}", error),
+        }
+    }
+}
+
+/// Writes `path` exactly as the file system has it, followed by a NUL, for `-0`.
+fn print_null_terminated(path: &Path) {
+    #[cfg(unix)]
+    let bytes = {
+        use std::os::unix::ffi::OsStrExt;
+        path.as_os_str().as_bytes()
+    };
+    
+    #[cfg(not(unix))]
+    let lossy = path.to_string_lossy();
+    #[cfg(not(unix))]
+    let bytes = lossy.as_bytes();
+    
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(bytes).ok();
+    stdout.write_all(b"\0").ok();
+    stdout.flush().ok();
+}
+
+// ==============================================
+// MACHINE-READABLE OUTPUT
+// ==============================================
+
+#[derive(Clone, Copy, PartialEq)]
+enum RecordFormat {
+    Json,
+    Csv,
+}
+
+impl RecordFormat {
+    fn for_output(output: OutputMode) -> Option<Self> {
+        match output {
+            OutputMode::Json => Some(RecordFormat::Json),
+            OutputMode::Csv => Some(RecordFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+fn print_csv_header() {
+    writeln!(io::stdout().lock(), "path,type,size,mime,system").ok();
+}
+
+/// Writes a match's path, type, size, the MIME type its first bytes show
+/// and the system it was found on, as a line of JSON or a CSV row. Size and
+/// MIME type are only given for files.
+fn print_record(format: RecordFormat, path: &Path) {
+    let metadata = fs::metadata(path).ok();
+    let kind = if metadata.as_ref().is_some_and(fs::Metadata::is_dir) { "directory" } else { "file" };
+    let size = metadata.filter(fs::Metadata::is_file).map(|metadata| metadata.len());
+    let mime = size.and_then(|_| detect_mime(path));
+    // Names that aren't valid UTF-8 are written the way the console shows them
+    let path_text = path.to_str().map_or_else(|| display_path(path), Cow::Borrowed);
+    
+    let line = match format {
+        RecordFormat::Json => format!("{{\"path\":{},\"type\":\"{}\",\"size\":{},\"mime\":{},\"system\":{}}}",
+            json_string(&path_text),
+            kind,
+            size.map_or_else(|| "null".to_string(), |size| size.to_string()),
+            mime.map_or_else(|| "null".to_string(), json_string),
+            json_string(&system_description())),
+        RecordFormat::Csv => format!("{},{},{},{},{}",
+            csv_field(&path_text),
+            kind,
+            size.map(|size| size.to_string()).unwrap_or_default(),
+            csv_field(mime.unwrap_or_default()),
+            csv_field(&system_description())),
+    };
+    writeln!(io::stdout().lock(), "{}", line).ok();
+}
+
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// Quoted only when it has to be, doubling any quotes inside
+fn csv_field(text: &str) -> Cow<'_, str> {
+    if text.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", text.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Fuzzy matches best first, with their scores. Scoring again here covers
+/// matches restored from a checkpoint, which carry no score.
+fn rank_matches<'a>(options: &Options, results: &'a [PathBuf]) -> Vec<(i32, &'a PathBuf)> {
+    let config = options.search_config();
+    let patterns: Vec<FuzzyPattern> = std::iter::once(&options.query)
+        .chain(&options.alternatives)
+        .map(|query| {
+            FuzzyPattern::new(query, config.case_sensitive_for(query))
+                .normalized(config.normalization())
+                .ignore_accents(config.ignores_accents())
+        })
+        .collect();
+    
+    let mut ranked: Vec<(i32, &PathBuf)> = results.iter()
+        .map(|path| {
+            let text = config.match_target().select(path, config.root_of(path)).unwrap_or_default().to_string_lossy();
+            let score = patterns.iter().filter_map(|pattern| pattern.score(&text)).max().unwrap_or(0);
+            (score, path)
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+    ranked
+}
+
+fn print_ranked(options: &Options, results: &[PathBuf]) {
+    println!();
+    for (score, path) in rank_matches(options, results) {
+        let kind = if path.is_dir() { "directory" } else { "file" };
+        println!("{:>5}  Found {}: {}", score, kind, display_path(path));
+    }
+}
+
+fn print_summary(stats: &SearchStats, stop_reason: StopReason) {
+    let seconds = stats.elapsed.as_secs_f32();
+    
+    match stop_reason {
+        StopReason::Completed => println!("\nSearch completed in {:.2} seconds!", seconds),
+        StopReason::Cancelled => println!("\nSearch cancelled after {:.2} seconds!", seconds),
+        StopReason::TimedOut => println!("\nSearch timed out after {:.2} seconds!", seconds),
+        StopReason::Stopped => println!("\nSearch stopped after {:.2} seconds!", seconds),
+    }
+    println!("Directories searched: {}", stats.dirs_searched);
+    if stop_reason != StopReason::Completed {
+        println!("Directories remaining: {}", stats.dirs_remaining);
+    }
+    println!("Files scanned: {}", stats.files_scanned);
+    println!("Matches found: {}", stats.matches_found);
+    if stats.io_errors > 0 {
+        println!("Unreadable entries: {}", stats.io_errors);
+    }
+}
+
+fn get_desktop_path() -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        if let Ok(user_profile) = env::var("USERPROFILE") {
+            PathBuf::from(user_profile).join("Desktop")
+        } else if let Ok(public) = env::var("PUBLIC") {
+            PathBuf::from(public).join("Desktop")
+        } else {
+            env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
+        }
+    }
+    
+    #[cfg(target_os = "macos")]
+    {
+        if let Ok(home) = env::var("HOME") {
+            PathBuf::from(home).join("Desktop")
+        } else {
+            env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
+        }
+    }
+    
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        if let Ok(home) = env::var("HOME") {
+            PathBuf::from(home).join("Desktop")
+        } else if let Ok(xdg_desktop) = env::var("XDG_DESKTOP_DIR") {
+            PathBuf::from(xdg_desktop)
+        } else {
+            env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
+        }
+    }
+}
+
+fn desktop_results_path() -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    
+    get_desktop_path().join(format!("FileSearch_Results_{}.log", timestamp))
+}
+
+// ==============================================
+// COMMAND LINE INTERFACE
+// ==============================================
+
+/// Prompts are only shown when someone is there to answer them, so
+/// pipelines and CI jobs never block on stdin.
+fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+fn confirm(question: &str) -> bool {
+    println!("{}", question);
+    let mut response = String::new();
+    io::stdin().read_line(&mut response).ok();
+    
+    response.trim().eq_ignore_ascii_case("y") || response.trim().eq_ignore_ascii_case("yes")
+}
+
+/// Asks whether to show the next page of matches; anything but "n" means yes.
+fn ask_for_more(shown: usize) -> bool {
+    print!("-- {} matches so far. Show more? [Y/n] ", shown);
+    io::stdout().flush().ok();
+    
+    let mut response = String::new();
+    if io::stdin().read_line(&mut response).unwrap_or(0) == 0 {
+        return false;
+    }
+    !response.trim().eq_ignore_ascii_case("n") && !response.trim().eq_ignore_ascii_case("no")
+}
+
+fn exit_code(engine: &SearchEngine, matches: usize) -> i32 {
+    // Stopping at a page prompt still answers the question, so it isn't incomplete
+    if matches!(engine.stop_reason(), StopReason::Cancelled | StopReason::TimedOut) {
+        EXIT_INCOMPLETE
+    } else if engine.stats().io_errors > 0 {
+        EXIT_IO_ERRORS
+    } else if matches > 0 {
+        EXIT_FOUND
+    } else {
+        EXIT_NOT_FOUND
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    
+    // The history subcommands; anything else is a search
+    let args = match args.first().map(String::as_str) {
+        Some("history") => std::process::exit(history_command(&args[1..])),
+        Some("save") => std::process::exit(save_command(&args[1..])),
+        Some("rerun") => match rerun_arguments(&args[1..]) {
+            Ok(args) => args,
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                std::process::exit(EXIT_USAGE);
+            }
+        },
+        _ => args,
+    };
+    
+    match parse_arguments(&args) {
+        Ok(mut options) => {
+            if options.output == OutputMode::Tui && !is_interactive() {
+                eprintln!("Error: --tui needs a terminal for input and output");
+                std::process::exit(EXIT_USAGE);
+            }
+            
+            let checkpoint = match options.resume_path.clone() {
+                Some(resume_path) => match Checkpoint::load(&resume_path) {
+                    Ok(checkpoint) => {
+                        options.apply_checkpoint(&checkpoint);
+                        // Keep checkpointing to the same file unless told otherwise
+                        if options.checkpoint_path.is_none() {
+                            options.checkpoint_path = Some(resume_path);
+                        }
+                        Some(checkpoint)
+                    }
+                    Err(err) => {
+                        eprintln!("Error: Cannot resume from '{}': {}", resume_path.display(), err);
+                        std::process::exit(EXIT_USAGE);
+                    }
+                },
+                None => None,
+            };
+            
+            if let Some(missing) = options.start_dirs.iter().find(|dir| !dir.exists()) {
+                eprintln!("Error: Start directory does not exist: {}",
+                         missing.display());
+                std::process::exit(EXIT_USAGE);
+            }
+            
+            let mut candidates = Vec::new();
+            if let Some(ref list_path) = options.paths_from {
+                let listed = match read_path_list(list_path) {
+                    Ok(listed) => listed,
+                    Err(e) => {
+                        eprintln!("Error: Cannot read path list '{}': {}", list_path.display(), e);
+                        std::process::exit(EXIT_USAGE);
+                    }
+                };
+                
+                // Directories are walked like start directories, everything
+                // else is matched as it is
+                for path in listed {
+                    if options.walk_listed_dirs && path.is_dir() {
+                        options.start_dirs.push(path);
+                    } else {
+                        candidates.push(path);
+                    }
+                }
+            }
+            
+            options.start_dirs = dedupe_roots(&options, options.start_dirs.clone());
+            
+            let mut engine = SearchEngine::new(options.search_config());
+            if let Some(checkpoint) = checkpoint {
+                if options.is_verbose() {
+                    println!("Resuming search: {} directories left, {} matches so far",
+                             checkpoint.pending_dirs.len(), checkpoint.found_items.len());
+                }
+                engine.restore(checkpoint);
+            }
+            engine.add_candidates(candidates);
+            
+            if options.is_verbose() {
+                if let Some(ref log_path) = options.log_path {
+                    println!("Logging to: {}", log_path.display());
+                }
+            }
+            
+            let engine = if options.output == OutputMode::Tui {
+                // Raw mode turns Ctrl-C into a keypress, so no handler is needed
+                match tui::run(engine, options.fuzzy.then(|| options.query.clone())) {
+                    Ok(engine) => engine,
+                    Err(e) => {
+                        eprintln!("Error: Cannot start the terminal UI: {}", e);
+                        std::process::exit(EXIT_USAGE);
+                    }
+                }
+            } else {
+                let mut reporter = ConsoleReporter::new(&options);
+                if options.output == OutputMode::Csv {
+                    print_csv_header();
+                }
+                interrupt::install(engine.cancel_flag());
+                let mut results = engine.into_results();
+                
+                // Fuzzy matches aren't printed as they come, so there is nothing to page
+                let page_size = options.page_size
+                    .filter(|_| options.is_verbose() && !options.fuzzy && is_interactive())
+                    .unwrap_or(usize::MAX);
+                let mut shown = 0;
+                let mut stopped_paging = false;
+                
+                // The walk waits while a page is read rather than racing ahead
+                loop {
+                    let page = results.next_page_with(page_size, |event| {
+                        // Matches already on their way are kept, just not shown
+                        if !(stopped_paging && matches!(event, SearchEvent::Match(_))) {
+                            reporter.handle(event);
+                        }
+                    });
+                    if page.len() < page_size {
+                        break;
+                    }
+                    
+                    shown += page.len();
+                    if !stopped_paging && !ask_for_more(shown) {
+                        stopped_paging = true;
+                        results.stop();
+                    }
+                }
+                let engine = results.finish();
+                // Ctrl-C at the save prompt should just quit as usual
+                interrupt::restore();
+                engine
+            };
+            
+            let results = engine.found_items();
+            
+            match options.output {
+                OutputMode::Quiet => {}
+                OutputMode::Count => println!("{}", results.len()),
+                OutputMode::Print0 => {
+                    if options.fuzzy {
+                        for (_, path) in rank_matches(&options, &results) {
+                            print_null_terminated(path);
+                        }
+                    }
+                }
+                OutputMode::Json | OutputMode::Csv => {
+                    if let Some(format) = RecordFormat::for_output(options.output).filter(|_| options.fuzzy) {
+                        for (_, path) in rank_matches(&options, &results) {
+                            print_record(format, path);
+                        }
+                    }
+                }
+                OutputMode::Normal | OutputMode::Tui => {
+                    if options.fuzzy && options.output == OutputMode::Normal && !results.is_empty() {
+                        print_ranked(&options, &results);
+                    }
+                    if !results.is_empty() {
+                        match engine.stop_reason() {
+                            StopReason::Completed => println!("\nFound {} matches.", results.len()),
+                            StopReason::Cancelled => println!("\nFound {} matches before the search was cancelled.", results.len()),
+                            StopReason::TimedOut => println!("\nFound {} matches before the search timed out.", results.len()),
+                            StopReason::Stopped => println!("\nFound {} matches before the search was stopped.", results.len()),
+                        }
+                        
+                        if options.log_path.is_some() {
+                            println!("Results logged to specified file.");
+                        }
+                    } else {
+                        println!("No matches found.");
+                    }
+                }
+            }
+            
+            let save_to = match options.save_policy {
+                SavePolicy::Path(ref path) => Some(path.clone()),
+                SavePolicy::Desktop if !results.is_empty() => Some(desktop_results_path()),
+                SavePolicy::Ask if !results.is_empty()
+                    && options.is_verbose()
+                    && options.log_path.is_none()
+                    && is_interactive() =>
+                {
+                    if confirm("Save results to desktop? [y/N]") { Some(desktop_results_path()) } else { None }
+                }
+                _ => None,
+            };
+            
+            if let Some(path) = save_to {
+                match engine.save_results(&path) {
+                    Ok(()) => if options.is_verbose() {
+                        println!("Results saved to: {}", path.display());
+                    },
+                    Err(e) => if options.shows_warnings() {
+                        eprintln!("Error saving results: {}", e);
+                    },
+                }
+            }
+            
+            if options.record_history {
+                if let Err(e) = record_search(&args, &options, results.len(), engine.stop_reason()) {
+                    if options.shows_warnings() {
+                        eprintln!("Warning: Cannot update search history: {}", e);
+                    }
+                }
+            }
+            
+            std::process::exit(exit_code(&engine, results.len()));
+        }
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            eprintln!("Run 'filesearch --help' for usage.");
+            std::process::exit(EXIT_USAGE);
+        }
+    }
+}
@@ -0,0 +1,1009 @@
+// Full-screen browsing of results as they come in (--tui). Drawing is
+// plain ANSI escape sequences; only switching the terminal in and out of
+// raw mode needs anything platform-specific.
+
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use filesearch_core::{display_path, FuzzyPattern, Match, Normalization, Pattern, SearchEngine, SearchEvent, SearchStats, StopReason};
+
+const SIZE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const REDRAW_INTERVAL: Duration = Duration::from_millis(50);
+
+enum UiEvent {
+    Search(SearchEvent),
+    SearchDone,
+    Input(Vec<u8>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Key {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Enter,
+    Escape,
+    Backspace,
+    Interrupt,
+    Char(char),
+}
+
+enum Prompt {
+    None,
+    // Editing the refinement filter
+    Filter,
+    // Waiting for y/n before deleting this result
+    ConfirmDelete(usize),
+}
+
+struct Browser {
+    matches: Vec<Match>,
+    // Indexes into `matches` that pass the filter, best first when ranking
+    visible: Vec<usize>,
+    // Each match's fuzzy score against the filter
+    scores: Vec<i32>,
+    filter: String,
+    // Set in fuzzy-finder mode, where the filter ranks rather than narrows
+    ranking: Option<FuzzyPattern>,
+    // The search's --normalize and --ignore-accents, so the filter compares
+    // names the same way
+    normalization: Normalization,
+    ignore_accents: bool,
+    selected: usize,
+    scroll: usize,
+    prompt: Prompt,
+    message: String,
+    stats: SearchStats,
+    rate: f32,
+    current_dir: Option<PathBuf>,
+    finished: Option<StopReason>,
+    // Deleted from here, so left out of the results along with anything
+    // found inside them
+    deleted: HashSet<PathBuf>,
+    rows: usize,
+    cols: usize,
+}
+
+/// Whether this platform has a terminal mode the list can run in.
+pub const SUPPORTED: bool = terminal::SUPPORTED;
+
+/// Runs the search behind a scrollable list of matches until the user
+/// quits, then hands the engine back for the usual exit code and history,
+/// without the matches that were deleted from the list.
+/// With `fuzzy_query` the list is a fuzzy finder instead: the engine is
+/// expected to report every name, and typing ranks them against the query.
+pub fn run(mut engine: SearchEngine, fuzzy_query: Option<String>) -> io::Result<SearchEngine> {
+    let (sender, receiver) = mpsc::channel();
+    
+    let events = engine.subscribe();
+    // Matches restored from a checkpoint were found before this run
+    let restored: Vec<Match> = engine.found_items().into_iter()
+        .map(|path| Match { is_dir: path.is_dir(), path, root: None, thread_id: None, score: None })
+        .collect();
+    
+    let normalization = engine.config().normalization();
+    let ignore_accents = engine.config().ignores_accents();
+    
+    // Set up the screen first so a failure leaves nothing running
+    let terminal = terminal::Raw::enable()?;
+    let search = engine.spawn();
+    
+    let search_sender = sender.clone();
+    thread::spawn(move || {
+        for event in events {
+            if search_sender.send(UiEvent::Search(event)).is_err() {
+                return;
+            }
+        }
+        let _ = search_sender.send(UiEvent::SearchDone);
+    });
+    
+    spawn_input_reader(sender);
+    
+    let (rows, cols) = terminal::size();
+    let mut browser = Browser::new(restored, fuzzy_query, normalization, ignore_accents, (rows, cols));
+    
+    let result = browser.event_loop(&receiver);
+    
+    // Quitting mid-search stops it; the workers still flush what they found
+    if browser.finished.is_none() {
+        search.cancel();
+    }
+    let mut engine = search.join();
+    drop(terminal);
+    for path in &browser.deleted {
+        engine.forget(path);
+    }
+    result.map(|()| engine)
+}
+
+fn spawn_input_reader(sender: Sender<UiEvent>) {
+    thread::spawn(move || {
+        let mut stdin = io::stdin();
+        let mut buf = [0u8; 64];
+        loop {
+            match stdin.read(&mut buf) {
+                Ok(0) | Err(_) => return,
+                Ok(n) => {
+                    if sender.send(UiEvent::Input(buf[..n].to_vec())).is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Splits raw terminal input into keys. Escape sequences arrive in one
+/// read, so a lone ESC at the end of a chunk is the Escape key itself.
+fn parse_keys(input: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let text = String::from_utf8_lossy(input);
+    let mut chars = text.chars().peekable();
+    
+    while let Some(c) = chars.next() {
+        let key = match c {
+            '\x1b' => {
+                if chars.peek() != Some(&'[') && chars.peek() != Some(&'O') {
+                    keys.push(Key::Escape);
+                    continue;
+                }
+                chars.next();
+                let mut sequence = String::new();
+                while let Some(&next) = chars.peek() {
+                    chars.next();
+                    sequence.push(next);
+                    if next.is_ascii_alphabetic() || next == '~' {
+                        break;
+                    }
+                }
+                match sequence.as_str() {
+                    "A" => Key::Up,
+                    "B" => Key::Down,
+                    "5~" => Key::PageUp,
+                    "6~" => Key::PageDown,
+                    "H" | "1~" | "7~" => Key::Home,
+                    "F" | "4~" | "8~" => Key::End,
+                    _ => continue,
+                }
+            }
+            '\r' | '\n' => Key::Enter,
+            '\x7f' | '\x08' => Key::Backspace,
+            '\x03' => Key::Interrupt,
+            c if c.is_control() => continue,
+            c => Key::Char(c),
+        };
+        keys.push(key);
+    }
+    
+    keys
+}
+
+impl Browser {
+    fn new(
+        matches: Vec<Match>,
+        fuzzy_query: Option<String>,
+        normalization: Normalization,
+        ignore_accents: bool,
+        (rows, cols): (usize, usize),
+    ) -> Self {
+        let mut browser = Browser {
+            matches,
+            visible: Vec::new(),
+            scores: Vec::new(),
+            ranking: fuzzy_query.as_ref().map(|_| FuzzyPattern::new("", false)),
+            // The finder starts out taking the query
+            prompt: if fuzzy_query.is_some() { Prompt::Filter } else { Prompt::None },
+            filter: fuzzy_query.unwrap_or_default(),
+            normalization,
+            ignore_accents,
+            selected: 0,
+            scroll: 0,
+            message: String::new(),
+            stats: SearchStats::default(),
+            rate: 0.0,
+            current_dir: None,
+            finished: None,
+            deleted: HashSet::new(),
+            rows,
+            cols,
+        };
+        browser.refilter();
+        browser
+    }
+    
+    fn event_loop(&mut self, receiver: &Receiver<UiEvent>) -> io::Result<()> {
+        let mut last_size_check = Instant::now();
+        let mut last_draw = Instant::now() - REDRAW_INTERVAL;
+        let mut dirty = true;
+        
+        loop {
+            match receiver.recv_timeout(REDRAW_INTERVAL) {
+                Ok(UiEvent::Search(event)) => {
+                    self.on_search_event(event);
+                    dirty = true;
+                }
+                Ok(UiEvent::SearchDone) => dirty = true,
+                Ok(UiEvent::Input(input)) => {
+                    for key in parse_keys(&input) {
+                        if !self.on_key(key) {
+                            return Ok(());
+                        }
+                    }
+                    // Answer keypresses straight away
+                    self.draw()?;
+                    last_draw = Instant::now();
+                    dirty = false;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+            
+            if last_size_check.elapsed() >= SIZE_CHECK_INTERVAL {
+                let size = terminal::size();
+                if size != (self.rows, self.cols) {
+                    (self.rows, self.cols) = size;
+                    dirty = true;
+                }
+                last_size_check = Instant::now();
+            }
+            
+            if dirty && last_draw.elapsed() >= REDRAW_INTERVAL {
+                self.draw()?;
+                last_draw = Instant::now();
+                dirty = false;
+            }
+        }
+    }
+    
+    fn on_search_event(&mut self, event: SearchEvent) {
+        match event {
+            SearchEvent::Match(found) => {
+                // Found inside a directory that was deleted since
+                if self.is_deleted(&found.path) {
+                    return;
+                }
+                let index = self.matches.len();
+                let score = self.filter_score(&found);
+                self.matches.push(found);
+                self.scores.push(score.unwrap_or(0));
+                
+                if score.is_some() {
+                    let position = if self.ranking.is_some() {
+                        let key = self.rank_key(index);
+                        self.visible.partition_point(|&other| self.rank_key(other) < key)
+                    } else {
+                        self.visible.len()
+                    };
+                    self.visible.insert(position, index);
+                }
+            }
+            SearchEvent::DirEntered { path, .. } => self.current_dir = Some(path),
+            SearchEvent::Progress { dirs, files, matches, rate } => {
+                self.stats.dirs_searched = dirs;
+                self.stats.files_scanned = files;
+                self.stats.matches_found = matches;
+                self.rate = rate;
+            }
+            SearchEvent::Finished { stats, reason } => {
+                self.stats = stats;
+                self.rate = stats.files_per_sec();
+                self.finished = Some(reason);
+                self.current_dir = None;
+            }
+            SearchEvent::Started { .. } | SearchEvent::Error(_) | SearchEvent::Stopping { .. } => {}
+        }
+    }
+    
+    /// Handles one key; returns false to quit.
+    fn on_key(&mut self, key: Key) -> bool {
+        if key == Key::Interrupt {
+            return false;
+        }
+        
+        match self.prompt {
+            Prompt::Filter => {
+                match key {
+                    Key::Enter => self.prompt = Prompt::None,
+                    Key::Escape => {
+                        self.prompt = Prompt::None;
+                        self.filter.clear();
+                        self.refilter();
+                    }
+                    Key::Backspace => {
+                        self.filter.pop();
+                        self.refilter();
+                    }
+                    Key::Char(c) => {
+                        self.filter.push(c);
+                        self.refilter();
+                    }
+                    _ => self.move_selection(key),
+                }
+                return true;
+            }
+            Prompt::ConfirmDelete(index) => {
+                self.prompt = Prompt::None;
+                if key == Key::Char('y') || key == Key::Char('Y') {
+                    self.delete(index);
+                } else {
+                    self.message = "Nothing deleted.".to_string();
+                }
+                return true;
+            }
+            Prompt::None => {}
+        }
+        
+        self.message.clear();
+        match key {
+            Key::Char('q') => return false,
+            Key::Char('/') => self.prompt = Prompt::Filter,
+            Key::Escape => {
+                self.filter.clear();
+                self.refilter();
+            }
+            Key::Char('j') => self.move_selection(Key::Down),
+            Key::Char('k') => self.move_selection(Key::Up),
+            Key::Char('g') => self.move_selection(Key::Home),
+            Key::Char('G') => self.move_selection(Key::End),
+            Key::Enter | Key::Char('o') => self.with_selected(|path| open_path(path).map(|()| "Opened".to_string())),
+            Key::Char('c') => self.with_selected(|path| copy_path(path).map(|()| "Copied path of".to_string())),
+            Key::Char('r') => self.with_selected(|path| reveal_path(path).map(|()| "Revealed".to_string())),
+            Key::Char('d') => {
+                if let Some(&index) = self.visible.get(self.selected) {
+                    self.prompt = Prompt::ConfirmDelete(index);
+                }
+            }
+            _ => self.move_selection(key),
+        }
+        
+        true
+    }
+    
+    fn move_selection(&mut self, key: Key) {
+        let page = self.list_rows().max(1);
+        let last = self.visible.len().saturating_sub(1);
+        
+        self.selected = match key {
+            Key::Up => self.selected.saturating_sub(1),
+            Key::Down => (self.selected + 1).min(last),
+            Key::PageUp => self.selected.saturating_sub(page),
+            Key::PageDown => (self.selected + page).min(last),
+            Key::Home => 0,
+            Key::End => last,
+            _ => self.selected,
+        };
+    }
+    
+    fn with_selected(&mut self, action: impl FnOnce(&Path) -> io::Result<String>) {
+        let Some(&index) = self.visible.get(self.selected) else {
+            return;
+        };
+        let path = self.matches[index].path.clone();
+        
+        self.message = match action(&path) {
+            Ok(done) => format!("{} {}", done, display_path(&path)),
+            Err(e) => format!("Error: {}", e),
+        };
+    }
+    
+    fn delete(&mut self, index: usize) {
+        let found = &self.matches[index];
+        let result = if found.is_dir {
+            std::fs::remove_dir_all(&found.path)
+        } else {
+            std::fs::remove_file(&found.path)
+        };
+        
+        match result {
+            Ok(()) => {
+                self.message = format!("Deleted {}", display_path(&found.path));
+                let path = found.path.clone();
+                self.matches.retain(|other| !other.path.starts_with(&path));
+                self.deleted.insert(path);
+                self.refilter();
+            }
+            Err(e) => self.message = format!("Cannot delete {}: {}", display_path(&found.path), e),
+        }
+    }
+    
+    fn is_deleted(&self, path: &Path) -> bool {
+        path.ancestors().any(|ancestor| self.deleted.contains(ancestor))
+    }
+    
+    /// Narrows the list to names matching the filter, without searching
+    /// again. A filter without wildcards matches anywhere in the name; in
+    /// the fuzzy finder the filter is scored instead. `None` hides it.
+    fn filter_score(&self, found: &Match) -> Option<i32> {
+        let name = match found.path.file_name() {
+            Some(name) => name,
+            None => return self.filter.is_empty().then_some(0),
+        };
+        
+        if let Some(ref ranking) = self.ranking {
+            ranking.score(&name.to_string_lossy())
+        } else if self.filter.is_empty() {
+            Some(0)
+        } else {
+            // Going through Pattern folds case the same way -i does
+            let mut pattern = Pattern::new(&self.filter, false, true);
+            if !pattern.is_wildcard() {
+                pattern = Pattern::new(&format!("*{}*", self.filter), false, true);
+            }
+            pattern
+                .normalized(self.normalization)
+                .ignore_accents(self.ignore_accents)
+                .matches_os(name)
+                .then_some(0)
+        }
+    }
+    
+    // Best score first; among equals, shorter names are the likelier pick
+    fn rank_key(&self, index: usize) -> (Reverse<i32>, usize, usize) {
+        let found = &self.matches[index];
+        let length = found.path.file_name().map_or(0, |name| name.len());
+        (Reverse(self.scores[index]), length, index)
+    }
+    
+    fn refilter(&mut self) {
+        if self.ranking.is_some() {
+            self.ranking = Some(FuzzyPattern::new(&self.filter, false)
+                .normalized(self.normalization)
+                .ignore_accents(self.ignore_accents));
+        }
+        
+        self.visible.clear();
+        self.scores.clear();
+        for index in 0..self.matches.len() {
+            let score = self.filter_score(&self.matches[index]);
+            self.scores.push(score.unwrap_or(0));
+            if score.is_some() {
+                self.visible.push(index);
+            }
+        }
+        
+        if self.ranking.is_some() {
+            let mut visible = std::mem::take(&mut self.visible);
+            visible.sort_by_cached_key(|&index| self.rank_key(index));
+            self.visible = visible;
+            // The best match is usually the one wanted
+            self.selected = 0;
+        }
+        self.selected = self.selected.min(self.visible.len().saturating_sub(1));
+    }
+    
+    // Everything but the title, status and help lines
+    fn list_rows(&self) -> usize {
+        self.rows.saturating_sub(3)
+    }
+    
+    fn draw(&mut self) -> io::Result<()> {
+        let list_rows = self.list_rows();
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if list_rows > 0 && self.selected >= self.scroll + list_rows {
+            self.scroll = self.selected + 1 - list_rows;
+        }
+        
+        let mut frame = String::from("\x1b[H");
+        
+        let title = match self.prompt {
+            Prompt::Filter if self.ranking.is_some() => format!(" Find: {}_  ({} of {})", self.filter, self.visible.len(), self.matches.len()),
+            Prompt::Filter => format!(" Filter: {}_", self.filter),
+            _ if !self.filter.is_empty() => format!(" FileSearch - showing {} of {} (filter: {})", self.visible.len(), self.matches.len(), self.filter),
+            _ => format!(" FileSearch - {} matches", self.matches.len()),
+        };
+        self.push_line(&mut frame, &title, "\x1b[1m", false);
+        frame.push_str("\r\n");
+        
+        for row in 0..list_rows {
+            let position = self.scroll + row;
+            match self.visible.get(position) {
+                Some(&index) => {
+                    let found = &self.matches[index];
+                    let marker = if found.is_dir { "[DIR] " } else { "      " };
+                    let line = format!(" {}{}", marker, display_path(&found.path));
+                    let style = if position == self.selected { "\x1b[7m" } else { "" };
+                    self.push_line(&mut frame, &line, style, true);
+                }
+                None => self.push_line(&mut frame, "", "", false),
+            }
+            frame.push_str("\r\n");
+        }
+        
+        let state = match self.finished {
+            None => match self.current_dir {
+                Some(ref dir) => format!("Searching {}", display_path(dir)),
+                None => "Searching".to_string(),
+            },
+            Some(StopReason::Completed) => format!("Completed in {:.2}s", self.stats.elapsed.as_secs_f32()),
+            Some(StopReason::Cancelled) => "Cancelled".to_string(),
+            Some(StopReason::TimedOut) => "Timed out".to_string(),
+            Some(StopReason::Stopped) => "Stopped".to_string(),
+        };
+        let status = format!(" {} dirs | {} files | {} matches | {:.1} files/sec | {}",
+            self.stats.dirs_searched, self.stats.files_scanned, self.matches.len(), self.rate, state);
+        self.push_line(&mut frame, &status, "\x1b[7m", false);
+        frame.push_str("\r\n");
+        
+        let help = match self.prompt {
+            Prompt::ConfirmDelete(index) => {
+                let found = &self.matches[index];
+                let what = if found.is_dir { "directory and everything in it" } else { "file" };
+                format!(" Delete {} ({})? y/n", display_path(&found.path), what)
+            }
+            Prompt::Filter if self.ranking.is_some() => " Type to search, Enter to pick from the list, Esc to clear".to_string(),
+            Prompt::Filter => " Type to refine, Enter to keep, Esc to clear".to_string(),
+            Prompt::None if !self.message.is_empty() => format!(" {}", self.message),
+            Prompt::None => " Up/Down move  / refine  Enter open  c copy path  r reveal  d delete  q quit".to_string(),
+        };
+        // No line break after the last row, or the screen would scroll
+        self.push_line(&mut frame, &help, "", false);
+        
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(frame.as_bytes())?;
+        stdout.flush()
+    }
+    
+    /// Adds one screen line, cut to the terminal width. Paths lose their
+    /// start rather than their end, since the file name is what matters.
+    fn push_line(&self, frame: &mut String, text: &str, style: &str, is_path: bool) {
+        let width = self.cols.max(1);
+        let chars: Vec<char> = text.chars().collect();
+        let shown: String = if chars.len() <= width {
+            text.to_string()
+        } else if is_path {
+            std::iter::once('…').chain(chars[chars.len() + 1 - width..].iter().copied()).collect()
+        } else {
+            chars[..width].iter().collect()
+        };
+        
+        frame.push_str(style);
+        frame.push_str(&shown);
+        if !style.is_empty() {
+            // Fill the line so highlighted rows look like bars
+            frame.push_str(&" ".repeat(width - shown.chars().count()));
+            frame.push_str("\x1b[0m");
+        }
+        frame.push_str("\x1b[K");
+    }
+}
+
+// ==============================================
+// ACTIONS ON A RESULT
+// ==============================================
+
+fn spawn_quietly(command: &mut Command) -> io::Result<()> {
+    command.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+fn open_path(path: &Path) -> io::Result<()> {
+    #[cfg(target_os = "windows")]
+    {
+        spawn_quietly(Command::new("cmd").args(["/C", "start", ""]).arg(path))
+    }
+    
+    #[cfg(target_os = "macos")]
+    {
+        spawn_quietly(Command::new("open").arg(path))
+    }
+    
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        spawn_quietly(Command::new("xdg-open").arg(path))
+    }
+}
+
+/// Shows the result in the system file manager.
+fn reveal_path(path: &Path) -> io::Result<()> {
+    #[cfg(target_os = "windows")]
+    {
+        let mut select = std::ffi::OsString::from("/select,");
+        select.push(path);
+        spawn_quietly(Command::new("explorer").arg(select))
+    }
+    
+    #[cfg(target_os = "macos")]
+    {
+        spawn_quietly(Command::new("open").arg("-R").arg(path))
+    }
+    
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        // File managers have no common way to select a file, so open its folder
+        let folder = path.parent().unwrap_or(path);
+        spawn_quietly(Command::new("xdg-open").arg(folder))
+    }
+}
+
+/// Puts the path on the clipboard with the platform's tool, or failing
+/// that asks the terminal to do it (OSC 52, works over SSH too).
+fn copy_path(path: &Path) -> io::Result<()> {
+    let text = path.to_string_lossy().into_owned();
+    
+    let tools: &[(&str, &[&str])] = if cfg!(target_os = "windows") {
+        &[("clip", &[])]
+    } else if cfg!(target_os = "macos") {
+        &[("pbcopy", &[])]
+    } else {
+        &[("wl-copy", &[]), ("xclip", &["-selection", "clipboard"]), ("xsel", &["--clipboard", "--input"])]
+    };
+    
+    for (tool, args) in tools {
+        let child = Command::new(tool)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        if let Ok(mut child) = child {
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(text.as_bytes())?;
+            }
+            if child.wait()?.success() {
+                return Ok(());
+            }
+        }
+    }
+    
+    let mut stdout = io::stdout().lock();
+    write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    stdout.flush()
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    
+    encoded
+}
+
+// ==============================================
+// TERMINAL MODES
+// ==============================================
+
+#[cfg(unix)]
+mod terminal {
+    use std::io::{self, Write};
+    use std::process::{Command, Stdio};
+    
+    pub const SUPPORTED: bool = true;
+    
+    /// Raw input on the alternate screen until dropped. stty saves us
+    /// from depending on the platform's termios layout.
+    pub struct Raw {
+        saved: String,
+    }
+    
+    fn stty(args: &[&str]) -> io::Result<String> {
+        let output = Command::new("stty")
+            .args(args)
+            .stdin(Stdio::inherit())
+            .stderr(Stdio::null())
+            .output()?;
+        if !output.status.success() {
+            return Err(io::Error::other("stty failed; is stdin a terminal?"));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+    
+    impl Raw {
+        pub fn enable() -> io::Result<Raw> {
+            let saved = stty(&["-g"])?;
+            stty(&["raw", "-echo"])?;
+            print!("\x1b[?1049h\x1b[?25l\x1b[2J");
+            io::stdout().flush()?;
+            Ok(Raw { saved })
+        }
+    }
+    
+    impl Drop for Raw {
+        fn drop(&mut self) {
+            print!("\x1b[?25h\x1b[?1049l");
+            let _ = io::stdout().flush();
+            let _ = stty(&[&self.saved]);
+        }
+    }
+    
+    /// Rows and columns, falling back to 24x80.
+    pub fn size() -> (usize, usize) {
+        stty(&["size"]).ok()
+            .and_then(|size| {
+                let (rows, cols) = size.split_once(' ')?;
+                Some((rows.parse().ok()?, cols.parse().ok()?))
+            })
+            .unwrap_or((24, 80))
+    }
+}
+
+#[cfg(windows)]
+mod terminal {
+    use std::io::{self, Write};
+    
+    pub const SUPPORTED: bool = true;
+    
+    const STD_INPUT_HANDLE: u32 = -10i32 as u32;
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+    const ENABLE_PROCESSED_INPUT: u32 = 0x0001;
+    const ENABLE_LINE_INPUT: u32 = 0x0002;
+    const ENABLE_ECHO_INPUT: u32 = 0x0004;
+    const ENABLE_VIRTUAL_TERMINAL_INPUT: u32 = 0x0200;
+    const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+    
+    #[repr(C)]
+    #[derive(Default)]
+    struct Coord {
+        x: i16,
+        y: i16,
+    }
+    
+    #[repr(C)]
+    #[derive(Default)]
+    struct SmallRect {
+        left: i16,
+        top: i16,
+        right: i16,
+        bottom: i16,
+    }
+    
+    #[repr(C)]
+    #[derive(Default)]
+    struct ScreenBufferInfo {
+        size: Coord,
+        cursor_position: Coord,
+        attributes: u16,
+        window: SmallRect,
+        maximum_window_size: Coord,
+    }
+    
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetStdHandle(handle: u32) -> isize;
+        fn GetConsoleMode(handle: isize, mode: *mut u32) -> i32;
+        fn SetConsoleMode(handle: isize, mode: u32) -> i32;
+        fn GetConsoleScreenBufferInfo(handle: isize, info: *mut ScreenBufferInfo) -> i32;
+    }
+    
+    /// Raw input on the alternate screen until dropped.
+    pub struct Raw {
+        input_mode: u32,
+        output_mode: u32,
+    }
+    
+    fn console_mode(handle: u32) -> io::Result<u32> {
+        let mut mode = 0;
+        if unsafe { GetConsoleMode(GetStdHandle(handle), &mut mode) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(mode)
+    }
+    
+    impl Raw {
+        pub fn enable() -> io::Result<Raw> {
+            let input_mode = console_mode(STD_INPUT_HANDLE)?;
+            let output_mode = console_mode(STD_OUTPUT_HANDLE)?;
+            
+            let raw_input = (input_mode & !(ENABLE_PROCESSED_INPUT | ENABLE_LINE_INPUT | ENABLE_ECHO_INPUT))
+                | ENABLE_VIRTUAL_TERMINAL_INPUT;
+            unsafe {
+                if SetConsoleMode(GetStdHandle(STD_INPUT_HANDLE), raw_input) == 0
+                    || SetConsoleMode(GetStdHandle(STD_OUTPUT_HANDLE), output_mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) == 0
+                {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            
+            print!("\x1b[?1049h\x1b[?25l\x1b[2J");
+            io::stdout().flush()?;
+            Ok(Raw { input_mode, output_mode })
+        }
+    }
+    
+    impl Drop for Raw {
+        fn drop(&mut self) {
+            print!("\x1b[?25h\x1b[?1049l");
+            let _ = io::stdout().flush();
+            unsafe {
+                SetConsoleMode(GetStdHandle(STD_INPUT_HANDLE), self.input_mode);
+                SetConsoleMode(GetStdHandle(STD_OUTPUT_HANDLE), self.output_mode);
+            }
+        }
+    }
+    
+    /// Rows and columns of the visible window, falling back to 24x80.
+    pub fn size() -> (usize, usize) {
+        let mut info = ScreenBufferInfo::default();
+        if unsafe { GetConsoleScreenBufferInfo(GetStdHandle(STD_OUTPUT_HANDLE), &mut info) } == 0 {
+            return (24, 80);
+        }
+        let rows = (info.window.bottom - info.window.top + 1) as usize;
+        let cols = (info.window.right - info.window.left + 1) as usize;
+        (rows, cols)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod terminal {
+    use std::io;
+    
+    pub const SUPPORTED: bool = false;
+    
+    pub struct Raw;
+    
+    impl Raw {
+        pub fn enable() -> io::Result<Raw> {
+            Err(io::Error::new(io::ErrorKind::Unsupported, "--tui isn't supported on this platform"))
+        }
+    }
+    
+    pub fn size() -> (usize, usize) {
+        (24, 80)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    
+    fn found(path: impl Into<PathBuf>, is_dir: bool) -> Match {
+        Match { path: path.into(), is_dir, root: None, thread_id: None, score: None }
+    }
+    
+    fn browser(paths: &[&str], fuzzy_query: Option<&str>) -> Browser {
+        let matches = paths.iter().map(|path| found(*path, false)).collect();
+        Browser::new(matches, fuzzy_query.map(str::to_string), Normalization::None, false, (10, 20))
+    }
+    
+    fn visible(browser: &Browser) -> Vec<String> {
+        browser.visible.iter()
+            .map(|&index| browser.matches[index].path.to_string_lossy().into_owned())
+            .collect()
+    }
+    
+    fn filtered(browser: &mut Browser, filter: &str) -> Vec<String> {
+        browser.filter = filter.to_string();
+        browser.refilter();
+        visible(browser)
+    }
+    
+    #[test]
+    fn keys_are_parsed_from_raw_input() {
+        assert_eq!(parse_keys(b"\x1b[A\x1b[B\x1bOA"), [Key::Up, Key::Down, Key::Up]);
+        assert_eq!(parse_keys(b"\x1b[5~\x1b[6~"), [Key::PageUp, Key::PageDown]);
+        assert_eq!(parse_keys(b"\x1b[H\x1b[1~\x1b[F\x1b[4~"), [Key::Home, Key::Home, Key::End, Key::End]);
+        assert_eq!(parse_keys(b"\r\n\x7f\x08\x03"), [Key::Enter, Key::Enter, Key::Backspace, Key::Backspace, Key::Interrupt]);
+        assert_eq!(parse_keys("qé".as_bytes()), [Key::Char('q'), Key::Char('é')]);
+    }
+    
+    #[test]
+    fn a_lone_escape_is_the_escape_key() {
+        assert_eq!(parse_keys(b"\x1b"), [Key::Escape]);
+        assert_eq!(parse_keys(b"\x1bq"), [Key::Escape, Key::Char('q')]);
+    }
+    
+    #[test]
+    fn unknown_sequences_and_control_characters_are_dropped() {
+        assert_eq!(parse_keys(b"\x1b[2~\x1b[15;2Ra"), [Key::Char('a')]);
+        assert_eq!(parse_keys(b"\x01\x1f"), []);
+    }
+    
+    #[test]
+    fn base64_matches_the_standard_vectors() {
+        for (text, encoded) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(base64(text.as_bytes()), encoded);
+        }
+        assert_eq!(base64(&[0xff, 0xfe, 0xfd]), "//79");
+    }
+    
+    #[test]
+    fn filters_match_anywhere_in_the_name_ignoring_case() {
+        let mut list = browser(&["src/Main.rs", "src/lib.rs", "main/notes.txt"], None);
+        assert_eq!(visible(&list).len(), 3);
+        assert_eq!(filtered(&mut list, "MAIN"), ["src/Main.rs"]);
+        assert_eq!(filtered(&mut list, "*.rs"), ["src/Main.rs", "src/lib.rs"]);
+        assert_eq!(filtered(&mut list, "l?b*"), ["src/lib.rs"]);
+        assert!(filtered(&mut list, "zzz").is_empty());
+        assert_eq!(filtered(&mut list, "").len(), 3);
+    }
+    
+    #[test]
+    fn escaped_wildcards_in_a_filter_match_anywhere_too() {
+        let mut list = browser(&["a/what?.txt", "a/whats.txt"], None);
+        assert_eq!(filtered(&mut list, "t\\?"), ["a/what?.txt"]);
+    }
+    
+    #[test]
+    fn the_fuzzy_finder_ranks_instead_of_narrowing() {
+        let mut list = browser(&["x/fast_user_report", "x/filesearch.rs", "x/other"], Some("fsr"));
+        assert_eq!(visible(&list), ["x/filesearch.rs", "x/fast_user_report"]);
+        assert_eq!(filtered(&mut list, "").len(), 3);
+    }
+    
+    #[test]
+    fn lines_are_cut_to_the_terminal_width() {
+        let list = browser(&[], None);
+        
+        let mut frame = String::new();
+        list.push_line(&mut frame, "short", "", false);
+        assert_eq!(frame, "short\x1b[K");
+        
+        frame.clear();
+        list.push_line(&mut frame, "a status line that is far too long", "", false);
+        assert_eq!(frame, "a status line that i\x1b[K");
+        
+        // Paths keep their end, where the name is
+        frame.clear();
+        list.push_line(&mut frame, "/home/me/projects/filesearch/main.rs", "", true);
+        assert_eq!(frame, "…/filesearch/main.rs\x1b[K");
+        
+        // Highlighted lines are padded out to the full width
+        frame.clear();
+        list.push_line(&mut frame, "pick", "\x1b[7m", false);
+        assert_eq!(frame, format!("\x1b[7mpick{}\x1b[0m\x1b[K", " ".repeat(16)));
+    }
+    
+    #[test]
+    fn deleting_a_directory_drops_everything_found_in_it() {
+        let dir = env::temp_dir().join(format!("filesearch-tui-delete-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("old/deeper")).unwrap();
+        fs::write(dir.join("old/deeper/a.log"), "x").unwrap();
+        fs::write(dir.join("keep.log"), "x").unwrap();
+        
+        let mut list = Browser::new(
+            vec![found(dir.join("old"), true), found(dir.join("old/deeper/a.log"), false), found(dir.join("keep.log"), false)],
+            None,
+            Normalization::None,
+            false,
+            (10, 80),
+        );
+        list.delete(0);
+        assert!(!dir.join("old").exists());
+        assert_eq!(list.matches.len(), 1);
+        assert_eq!(list.matches[0].path, dir.join("keep.log"));
+        
+        // Still on its way from the search when the directory went
+        list.on_search_event(SearchEvent::Match(found(dir.join("old/deeper/b.log"), false)));
+        assert_eq!(list.matches.len(), 1);
+        assert!(list.is_deleted(&dir.join("old/deeper")));
+        assert!(!list.is_deleted(&dir.join("keep.log")));
+        
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,151 @@
+#!/usr/bin/env python3
+"""Writes src/unicode_tables.rs from the Unicode Character Database.
+
+The UCD version is pinned below, so the tables don't depend on the Python
+that happens to run this. To move to a newer Unicode, change
+UNICODE_VERSION and run from filesearch-core/:
+
+    python3 scripts/gen_unicode_tables.py [UCD_DIR]
+
+The data files are downloaded from unicode.org, or read from UCD_DIR if
+given (e.g. an unpacked UCD.zip of the same version).
+"""
+
+import os
+import re
+import sys
+import urllib.request
+
+UNICODE_VERSION = "15.1.0"
+UCD_URL = "https://www.unicode.org/Public/%s/ucd/%s"
+FILES = ["UnicodeData.txt", "CaseFolding.txt", "DerivedNormalizationProps.txt"]
+
+OUTPUT = "src/unicode_tables.rs"
+
+
+def rust_char(cp):
+    return "'\\u{%x}'" % cp
+
+
+def rust_str(cps):
+    return '"' + "".join("\\u{%x}" % cp for cp in cps) + '"'
+
+
+def read_ucd_file(name, ucd_dir):
+    if ucd_dir:
+        with open(os.path.join(ucd_dir, name), encoding="utf-8") as f:
+            text = f.read()
+    else:
+        with urllib.request.urlopen(UCD_URL % (UNICODE_VERSION, name)) as response:
+            text = response.read().decode("utf-8")
+
+    # Files that carry their version must be the pinned one
+    stem = name[:-len(".txt")]
+    version = re.search(r"^# %s-(\d+\.\d+\.\d+)\.txt" % stem, text, re.MULTILINE)
+    if version and version.group(1) != UNICODE_VERSION:
+        sys.exit("%s is from Unicode %s, expected %s" % (name, version.group(1), UNICODE_VERSION))
+    return text
+
+
+def data_lines(text):
+    """The fields of each line that isn't blank or a comment."""
+    for line in text.splitlines():
+        line = line.split("#", 1)[0].strip()
+        if line:
+            yield [field.strip() for field in line.split(";")]
+
+
+def code_points(field):
+    return [int(cp, 16) for cp in field.split()]
+
+
+def code_point_range(field):
+    first, _, last = field.partition("..")
+    return range(int(first, 16), int(last or first, 16) + 1)
+
+
+def push_range(ranges, cp, value=None):
+    """Extends the last (first, last[, value]) range when cp continues it."""
+    if ranges and ranges[-1][1] + 1 == cp and ranges[-1][2:] == ((value,) if value is not None else ()):
+        ranges[-1] = (ranges[-1][0], cp) + ranges[-1][2:]
+    else:
+        ranges.append((cp, cp) + ((value,) if value is not None else ()))
+
+
+def main():
+    ucd_dir = sys.argv[1] if len(sys.argv) > 1 else None
+    unicode_data, case_folding, normalization_props = (read_ucd_file(name, ucd_dir) for name in FILES)
+
+    folds = []
+    for cp, status, mapping in ((f[0], f[1], f[2]) for f in data_lines(case_folding)):
+        # Full folding: the common mappings plus the ones that lengthen (ß to ss)
+        if status in ("C", "F"):
+            folds.append((int(cp, 16), code_points(mapping)))
+
+    excluded = set()
+    for fields in data_lines(normalization_props):
+        if fields[1] == "Full_Composition_Exclusion":
+            excluded.update(code_point_range(fields[0]))
+
+    decompositions = []
+    compositions = []
+    classes = []
+    marks = []
+
+    # Hangul syllables are listed as a range without decompositions, as
+    # they're algorithmic, and no range has marks or combining classes
+    for fields in data_lines(unicode_data):
+        cp = int(fields[0], 16)
+        category, ccc, decomposition = fields[2], int(fields[3]), fields[5]
+
+        if decomposition and not decomposition.startswith("<"):
+            parts = code_points(decomposition)
+            decompositions.append((cp, parts))
+            if cp not in excluded:
+                compositions.append((parts[0], parts[1], cp))
+
+        if ccc:
+            push_range(classes, cp, ccc)
+        if category == "Mn":
+            push_range(marks, cp)
+
+    folds.sort()
+    compositions.sort()
+
+    with open(OUTPUT, "w", encoding="utf-8") as out:
+        out.write("// Generated by scripts/gen_unicode_tables.py from Unicode %s; do not edit.\n\n"
+                  % UNICODE_VERSION)
+
+        out.write("/// Full case folding (CaseFolding.txt, statuses C and F).\n")
+        out.write("pub(crate) const CASE_FOLDING: &[(char, &str)] = &[\n")
+        for cp, folded in folds:
+            out.write("    (%s, %s),\n" % (rust_char(cp), rust_str(folded)))
+        out.write("];\n\n")
+
+        out.write("/// One level of canonical decomposition; Hangul syllables are algorithmic.\n")
+        out.write("pub(crate) const CANONICAL_DECOMPOSITION: &[(char, &str)] = &[\n")
+        for cp, parts in decompositions:
+            out.write("    (%s, %s),\n" % (rust_char(cp), rust_str(parts)))
+        out.write("];\n\n")
+
+        out.write("/// Primary composites: (starter, combining mark, composed).\n")
+        out.write("pub(crate) const CANONICAL_COMPOSITION: &[(char, char, char)] = &[\n")
+        for first, second, composed in compositions:
+            out.write("    (%s, %s, %s),\n" % (rust_char(first), rust_char(second), rust_char(composed)))
+        out.write("];\n\n")
+
+        out.write("/// Canonical combining classes other than 0, as inclusive ranges.\n")
+        out.write("pub(crate) const COMBINING_CLASS: &[(char, char, u8)] = &[\n")
+        for first, last, ccc in classes:
+            out.write("    (%s, %s, %d),\n" % (rust_char(first), rust_char(last), ccc))
+        out.write("];\n\n")
+
+        out.write("/// Nonspacing marks (general category Mn), as inclusive ranges.\n")
+        out.write("pub(crate) const NONSPACING_MARKS: &[(char, char)] = &[\n")
+        for first, last in marks:
+            out.write("    (%s, %s),\n" % (rust_char(first), rust_char(last)))
+        out.write("];\n")
+
+
+if __name__ == "__main__":
+    main()
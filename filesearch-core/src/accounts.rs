@@ -0,0 +1,127 @@
+//! User and group accounts, for matching files by owner.
+
+use std::collections::BTreeMap;
+use std::ffi::{c_char, c_int, c_void, CString};
+use std::ptr;
+use std::sync::Mutex;
+
+// Only the leading fields, which come first and in this order on Linux,
+// macOS and the BSDs. The structs are never made or sized on the Rust
+// side: the C library fills in one of its own in `entry` below, and a
+// field is read through a pointer to it, at the offset it has there too
+#[repr(C)]
+struct Passwd {
+    pw_name: *mut c_char,
+    pw_passwd: *mut c_char,
+    pw_uid: u32,
+    pw_gid: u32,
+}
+
+#[repr(C)]
+struct Group {
+    gr_name: *mut c_char,
+    gr_passwd: *mut c_char,
+    gr_gid: u32,
+}
+
+// The reentrant lookups fill in a caller's struct and string buffer
+type Lookup<K> = unsafe extern "C" fn(K, *mut c_void, *mut c_char, usize, *mut *mut c_void) -> c_int;
+
+extern "C" {
+    fn getpwnam_r(name: *const c_char, entry: *mut c_void, buffer: *mut c_char, size: usize, result: *mut *mut c_void) -> c_int;
+    fn getgrnam_r(name: *const c_char, entry: *mut c_void, buffer: *mut c_char, size: usize, result: *mut *mut c_void) -> c_int;
+    fn getpwuid_r(uid: u32, entry: *mut c_void, buffer: *mut c_char, size: usize, result: *mut *mut c_void) -> c_int;
+    fn getgrgid_r(gid: u32, entry: *mut c_void, buffer: *mut c_char, size: usize, result: *mut *mut c_void) -> c_int;
+}
+
+// Groups list their members, so their entries can be large
+const MAX_BUFFER: usize = 1 << 20;
+
+static KNOWN_USERS: Mutex<BTreeMap<u32, bool>> = Mutex::new(BTreeMap::new());
+static KNOWN_GROUPS: Mutex<BTreeMap<u32, bool>> = Mutex::new(BTreeMap::new());
+
+/// The ID of the user called `name`, or `name` itself if it's a number
+/// and no user has that name.
+pub fn user_id(name: &str) -> Option<u32> {
+    let c_name = CString::new(name).ok()?;
+    match look_up(c_name.as_ptr(), getpwnam_r, |entry| unsafe { (*entry.cast::<Passwd>()).pw_uid }) {
+        Ok(Some(uid)) => Some(uid),
+        _ => name.parse().ok(),
+    }
+}
+
+/// The ID of the group called `name`, or `name` itself if it's a number
+/// and no group has that name.
+pub fn group_id(name: &str) -> Option<u32> {
+    let c_name = CString::new(name).ok()?;
+    match look_up(c_name.as_ptr(), getgrnam_r, |entry| unsafe { (*entry.cast::<Group>()).gr_gid }) {
+        Ok(Some(gid)) => Some(gid),
+        _ => name.parse().ok(),
+    }
+}
+
+/// Whether any user has this ID. Safe to call from the search threads.
+pub(crate) fn user_exists(uid: u32) -> bool {
+    *KNOWN_USERS.lock().unwrap().entry(uid).or_insert_with(|| id_exists(uid, getpwuid_r))
+}
+
+/// Whether any group has this ID. Safe to call from the search threads.
+pub(crate) fn group_exists(gid: u32) -> bool {
+    *KNOWN_GROUPS.lock().unwrap().entry(gid).or_insert_with(|| id_exists(gid, getgrgid_r))
+}
+
+fn id_exists(id: u32, lookup: Lookup<u32>) -> bool {
+    // If the account database couldn't be read, don't report the file as orphaned
+    look_up(id, lookup, |_| ()).map_or(true, |found| found.is_some())
+}
+
+/// Looks up an account, handing the entry the C library filled in to
+/// `read`. `Ok(None)` if there's no such account, `Err` if the lookup
+/// failed even with the largest buffer.
+fn look_up<K: Copy, T>(key: K, lookup: Lookup<K>, read: impl FnOnce(*const c_void) -> T) -> Result<Option<T>, c_int> {
+    // Room for the whole struct, which is only declared in part above
+    let mut entry = [0u64; 32];
+    let mut buffer: Vec<c_char> = vec![0; 4096];
+    
+    loop {
+        let mut result = ptr::null_mut();
+        let status = unsafe { lookup(key, entry.as_mut_ptr().cast(), buffer.as_mut_ptr(), buffer.len(), &mut result) };
+        match status {
+            0 if result.is_null() => return Ok(None),
+            0 => return Ok(Some(read(result))),
+            // Usually ERANGE, for a buffer too small for the entry's
+            // strings. Rather than hard-code errno values, which come
+            // from each platform's headers, any error is retried with
+            // more room
+            _ if buffer.len() < MAX_BUFFER => buffer.resize(buffer.len() * 4, 0),
+            _ => return Err(status),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    
+    #[test]
+    fn names_and_numbers_are_looked_up() {
+        assert_eq!(user_id("root"), Some(0));
+        assert_eq!(user_id("no-such-user-for-filesearch"), None);
+        // A number no one is called is taken as an ID
+        assert_eq!(user_id("4242"), Some(4242));
+        assert_eq!(group_id("0"), Some(0));
+        assert_eq!(group_id("no-such-group-for-filesearch"), None);
+        assert_eq!(user_id("ro\0ot"), None);
+    }
+    
+    #[test]
+    fn lookups_can_run_on_many_threads() {
+        let handles: Vec<_> = (0..8)
+            .map(|_| thread::spawn(|| (0..50).all(|_| user_id("root") == Some(0) && user_exists(0) && group_exists(0))))
+            .collect();
+        for handle in handles {
+            assert!(handle.join().unwrap());
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::{FileKind, MatchTarget, SearchConfigBuilder, SearchMode};
+use crate::expression::Expression;
+use crate::permissions::PermissionFilter;
+use crate::unicode::Normalization;
+use crate::escape::{decode_path, encode_path, escape_text, unescape_text};
+
+const CHECKPOINT_HEADER: &str = "FileSearch Checkpoint v1";
+
+/// A snapshot of a search in progress, enough to pick it up where it left off.
+pub struct Checkpoint {
+    pub mode: SearchMode,
+    pub query: String,
+    pub alternatives: Vec<String>,
+    pub start_dirs: Vec<PathBuf>,
+    pub case_sensitive: bool,
+    pub smart_case: bool,
+    pub normalization: Normalization,
+    pub ignore_accents: bool,
+    pub use_wildcards: bool,
+    pub fuzzy: bool,
+    pub match_target: MatchTarget,
+    pub extensions: Vec<String>,
+    pub file_kinds: Vec<FileKind>,
+    pub only_empty: bool,
+    pub only_executable: bool,
+    pub user: Option<u32>,
+    pub group: Option<u32>,
+    pub no_user: bool,
+    pub no_group: bool,
+    pub permissions: Option<PermissionFilter>,
+    pub mime_types: Vec<String>,
+    pub expression: Option<Expression>,
+    pub max_depth: Option<usize>,
+    pub breadth_first: bool,
+    pub pending_dirs: VecDeque<PathBuf>,
+    pub searched_dirs: HashSet<PathBuf>,
+    pub found_items: HashSet<PathBuf>,
+    pub dirs_searched: usize,
+    pub files_scanned: usize,
+    pub matches_found: usize,
+}
+
+impl Checkpoint {
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        // Write next to the target and rename, so a crash mid-write
+        // never leaves a truncated checkpoint behind
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+        
+        let mut file = io::BufWriter::new(fs::File::create(&tmp_path)?);
+        
+        writeln!(file, "{}", CHECKPOINT_HEADER)?;
+        writeln!(file, "mode: {}", match self.mode {
+            SearchMode::File => "file",
+            SearchMode::Directory => "directory",
+            SearchMode::Both => "both",
+        })?;
+        writeln!(file, "query: {}", escape_text(self.query.as_bytes()))?;
+        for alternative in &self.alternatives {
+            writeln!(file, "or_query: {}", escape_text(alternative.as_bytes()))?;
+        }
+        for start_dir in &self.start_dirs {
+            writeln!(file, "start_dir: {}", encode_path(start_dir))?;
+        }
+        writeln!(file, "case_sensitive: {}", self.case_sensitive)?;
+        writeln!(file, "smart_case: {}", self.smart_case)?;
+        writeln!(file, "normalization: {}", match self.normalization {
+            Normalization::None => "none",
+            Normalization::Nfc => "nfc",
+            Normalization::Nfd => "nfd",
+        })?;
+        writeln!(file, "ignore_accents: {}", self.ignore_accents)?;
+        writeln!(file, "use_wildcards: {}", self.use_wildcards)?;
+        writeln!(file, "fuzzy: {}", self.fuzzy)?;
+        writeln!(file, "match_on: {}", match self.match_target {
+            MatchTarget::Name => "name",
+            MatchTarget::Path => "path",
+            MatchTarget::RelativePath => "relpath",
+            MatchTarget::Stem => "stem",
+            MatchTarget::Extension => "ext",
+        })?;
+        for extension in &self.extensions {
+            writeln!(file, "extension: {}", escape_text(extension.as_bytes()))?;
+        }
+        if !self.file_kinds.is_empty() {
+            let letters: Vec<String> = self.file_kinds.iter().map(|kind| kind.letter().to_string()).collect();
+            writeln!(file, "types: {}", letters.join(","))?;
+        }
+        writeln!(file, "empty: {}", self.only_empty)?;
+        writeln!(file, "executable: {}", self.only_executable)?;
+        if let Some(uid) = self.user {
+            writeln!(file, "user: {}", uid)?;
+        }
+        if let Some(gid) = self.group {
+            writeln!(file, "group: {}", gid)?;
+        }
+        writeln!(file, "nouser: {}", self.no_user)?;
+        writeln!(file, "nogroup: {}", self.no_group)?;
+        if let Some(filter) = self.permissions {
+            writeln!(file, "perm: {}", filter)?;
+        }
+        for mime_type in &self.mime_types {
+            writeln!(file, "mime: {}", escape_text(mime_type.as_bytes()))?;
+        }
+        if let Some(ref expression) = self.expression {
+            writeln!(file, "expression: {}", escape_text(expression.source().as_bytes()))?;
+        }
+        match self.max_depth {
+            Some(depth) => writeln!(file, "max_depth: {}", depth)?,
+            None => writeln!(file, "max_depth: none")?,
+        }
+        writeln!(file, "breadth_first: {}", self.breadth_first)?;
+        writeln!(file, "dirs_searched: {}", self.dirs_searched)?;
+        writeln!(file, "files_scanned: {}", self.files_scanned)?;
+        writeln!(file, "matches_found: {}", self.matches_found)?;
+        
+        writeln!(file, "[pending]")?;
+        for dir in &self.pending_dirs {
+            writeln!(file, "{}", encode_path(dir))?;
+        }
+        writeln!(file, "[searched]")?;
+        for dir in &self.searched_dirs {
+            writeln!(file, "{}", encode_path(dir))?;
+        }
+        writeln!(file, "[found]")?;
+        for path in &self.found_items {
+            writeln!(file, "{}", encode_path(path))?;
+        }
+        
+        file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        fs::rename(&tmp_path, path)
+    }
+    
+    pub fn load(path: &Path) -> Result<Checkpoint, String> {
+        let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut lines = contents.lines();
+        
+        if lines.next() != Some(CHECKPOINT_HEADER) {
+            return Err("not a FileSearch checkpoint file".to_string());
+        }
+        
+        let mut checkpoint = Checkpoint {
+            mode: SearchMode::Both,
+            query: String::new(),
+            alternatives: Vec::new(),
+            start_dirs: Vec::new(),
+            case_sensitive: true,
+            smart_case: false,
+            normalization: Normalization::None,
+            ignore_accents: false,
+            use_wildcards: true,
+            fuzzy: false,
+            match_target: MatchTarget::Name,
+            extensions: Vec::new(),
+            file_kinds: Vec::new(),
+            only_empty: false,
+            only_executable: false,
+            user: None,
+            group: None,
+            no_user: false,
+            no_group: false,
+            permissions: None,
+            mime_types: Vec::new(),
+            expression: None,
+            max_depth: None,
+            breadth_first: true,
+            pending_dirs: VecDeque::new(),
+            searched_dirs: HashSet::new(),
+            found_items: HashSet::new(),
+            dirs_searched: 0,
+            files_scanned: 0,
+            matches_found: 0,
+        };
+        
+        let mut section = "";
+        
+        for (index, line) in lines.enumerate() {
+            let line_no = index + 2;
+            let bad_line = || format!("invalid entry on line {}", line_no);
+            
+            if line.starts_with('[') && line.ends_with(']') {
+                section = match line {
+                    "[pending]" | "[searched]" | "[found]" => line,
+                    _ => return Err(format!("unknown section {} on line {}", line, line_no)),
+                };
+                continue;
+            }
+            
+            if !section.is_empty() {
+                let path = decode_path(line).ok_or_else(bad_line)?;
+                match section {
+                    "[pending]" => checkpoint.pending_dirs.push_back(path),
+                    "[searched]" => { checkpoint.searched_dirs.insert(path); }
+                    _ => { checkpoint.found_items.insert(path); }
+                }
+                continue;
+            }
+            
+            let (key, value) = line.split_once(": ").ok_or_else(bad_line)?;
+            match key {
+                "mode" => checkpoint.mode = match value {
+                    "file" => SearchMode::File,
+                    "directory" => SearchMode::Directory,
+                    "both" => SearchMode::Both,
+                    _ => return Err(bad_line()),
+                },
+                "query" => {
+                    let bytes = unescape_text(value).ok_or_else(bad_line)?;
+                    checkpoint.query = String::from_utf8(bytes).map_err(|_| bad_line())?;
+                }
+                "or_query" => {
+                    let bytes = unescape_text(value).ok_or_else(bad_line)?;
+                    checkpoint.alternatives.push(String::from_utf8(bytes).map_err(|_| bad_line())?);
+                }
+                "start_dir" => checkpoint.start_dirs.push(decode_path(value).ok_or_else(bad_line)?),
+                "case_sensitive" => checkpoint.case_sensitive = value.parse().map_err(|_| bad_line())?,
+                "smart_case" => checkpoint.smart_case = value.parse().map_err(|_| bad_line())?,
+                "normalization" => checkpoint.normalization = match value {
+                    "none" => Normalization::None,
+                    "nfc" => Normalization::Nfc,
+                    "nfd" => Normalization::Nfd,
+                    _ => return Err(bad_line()),
+                },
+                "ignore_accents" => checkpoint.ignore_accents = value.parse().map_err(|_| bad_line())?,
+                "use_wildcards" => checkpoint.use_wildcards = value.parse().map_err(|_| bad_line())?,
+                "fuzzy" => checkpoint.fuzzy = value.parse().map_err(|_| bad_line())?,
+                "match_on" => checkpoint.match_target = match value {
+                    "name" => MatchTarget::Name,
+                    "path" => MatchTarget::Path,
+                    "relpath" => MatchTarget::RelativePath,
+                    "stem" => MatchTarget::Stem,
+                    "ext" => MatchTarget::Extension,
+                    _ => return Err(bad_line()),
+                },
+                "extension" => {
+                    let bytes = unescape_text(value).ok_or_else(bad_line)?;
+                    checkpoint.extensions.push(String::from_utf8(bytes).map_err(|_| bad_line())?);
+                }
+                "types" => {
+                    for letter in value.split(',') {
+                        let mut chars = letter.chars();
+                        let kind = chars.next().and_then(FileKind::from_letter).ok_or_else(bad_line)?;
+                        if chars.next().is_some() {
+                            return Err(bad_line());
+                        }
+                        checkpoint.file_kinds.push(kind);
+                    }
+                }
+                "empty" => checkpoint.only_empty = value.parse().map_err(|_| bad_line())?,
+                "executable" => checkpoint.only_executable = value.parse().map_err(|_| bad_line())?,
+                "user" => checkpoint.user = Some(value.parse().map_err(|_| bad_line())?),
+                "group" => checkpoint.group = Some(value.parse().map_err(|_| bad_line())?),
+                "nouser" => checkpoint.no_user = value.parse().map_err(|_| bad_line())?,
+                "nogroup" => checkpoint.no_group = value.parse().map_err(|_| bad_line())?,
+                "mime" => {
+                    let bytes = unescape_text(value).ok_or_else(bad_line)?;
+                    checkpoint.mime_types.push(String::from_utf8(bytes).map_err(|_| bad_line())?);
+                }
+                "perm" => checkpoint.permissions = Some(PermissionFilter::parse(value).map_err(|_| bad_line())?),
+                "expression" => {
+                    let bytes = unescape_text(value).ok_or_else(bad_line)?;
+                    let source = String::from_utf8(bytes).map_err(|_| bad_line())?;
+                    checkpoint.expression = Some(Expression::parse(&source)
+                        .map_err(|e| format!("invalid expression on line {}: {}", line_no, e))?);
+                }
+                "max_depth" => checkpoint.max_depth = match value {
+                    "none" => None,
+                    _ => Some(value.parse().map_err(|_| bad_line())?),
+                },
+                "breadth_first" => checkpoint.breadth_first = value.parse().map_err(|_| bad_line())?,
+                "dirs_searched" => checkpoint.dirs_searched = value.parse().map_err(|_| bad_line())?,
+                "files_scanned" => checkpoint.files_scanned = value.parse().map_err(|_| bad_line())?,
+                "matches_found" => checkpoint.matches_found = value.parse().map_err(|_| bad_line())?,
+                _ => return Err(format!("unknown key '{}' on line {}", key, line_no)),
+            }
+        }
+        
+        Ok(checkpoint)
+    }
+    
+    /// Copies the settings that define the search into `builder`. Runtime
+    /// options like thread count and log file are left as they are.
+    pub fn apply_to(&self, builder: SearchConfigBuilder) -> SearchConfigBuilder {
+        builder
+            .mode(self.mode)
+            .query(self.query.clone())
+            .alternatives(self.alternatives.clone())
+            .start_dirs(self.start_dirs.clone())
+            .case_sensitive(self.case_sensitive)
+            .smart_case(self.smart_case)
+            .normalization(self.normalization)
+            .ignore_accents(self.ignore_accents)
+            .wildcards(self.use_wildcards)
+            .fuzzy(self.fuzzy)
+            .match_on(self.match_target)
+            .extensions(self.extensions.clone())
+            .file_kinds(self.file_kinds.clone())
+            .only_empty(self.only_empty)
+            .only_executable(self.only_executable)
+            .user(self.user)
+            .group(self.group)
+            .no_user(self.no_user)
+            .no_group(self.no_group)
+            .permissions(self.permissions)
+            .mime_types(self.mime_types.clone())
+            .expression(self.expression.clone())
+            .max_depth(self.max_depth)
+            .breadth_first(self.breadth_first)
+    }
+}
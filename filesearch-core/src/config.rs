@@ -0,0 +1,538 @@
+use std::ffi::OsStr;
+use std::fs::FileType;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::expression::Expression;
+use crate::permissions::PermissionFilter;
+use crate::unicode::{has_uppercase, Normalization};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SearchMode {
+    File,
+    Directory,
+    Both,
+}
+
+/// Which part of a path the query is matched against.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MatchTarget {
+    /// The file or directory name.
+    Name,
+    /// The whole path, starting with the start directory it was found under.
+    Path,
+    /// The path below the start directory it was found under.
+    RelativePath,
+    /// The name without its extension.
+    Stem,
+    /// The extension, without the dot.
+    Extension,
+}
+
+impl MatchTarget {
+    /// The part of `path` this target looks at, or `None` if it has no
+    /// such part, like a name without an extension. `root` is the start
+    /// directory the path was found under; listed paths have none and are
+    /// matched whole.
+    pub fn select<'a>(self, path: &'a Path, root: Option<&Path>) -> Option<&'a OsStr> {
+        match self {
+            MatchTarget::Name => path.file_name(),
+            MatchTarget::Path => Some(path.as_os_str()),
+            MatchTarget::RelativePath => {
+                let relative = root.and_then(|root| path.strip_prefix(root).ok()).unwrap_or(path);
+                Some(relative.as_os_str())
+            }
+            MatchTarget::Stem => path.file_stem(),
+            MatchTarget::Extension => path.extension(),
+        }
+    }
+}
+
+/// What kind of file system entry something is, as `find -type` tells them apart.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FileKind {
+    File,
+    Directory,
+    Symlink,
+    Pipe,
+    Socket,
+    BlockDevice,
+    CharDevice,
+}
+
+impl FileKind {
+    /// Symlinks are reported as such, not as what they point to.
+    pub fn of(file_type: &FileType) -> Self {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            if file_type.is_fifo() {
+                return FileKind::Pipe;
+            } else if file_type.is_socket() {
+                return FileKind::Socket;
+            } else if file_type.is_block_device() {
+                return FileKind::BlockDevice;
+            } else if file_type.is_char_device() {
+                return FileKind::CharDevice;
+            }
+        }
+        
+        if file_type.is_symlink() {
+            FileKind::Symlink
+        } else if file_type.is_dir() {
+            FileKind::Directory
+        } else {
+            FileKind::File
+        }
+    }
+    
+    /// The kind for one of `find -type`'s letters: f, d, l, p, s, b or c.
+    pub fn from_letter(letter: char) -> Option<Self> {
+        Some(match letter {
+            'f' => FileKind::File,
+            'd' => FileKind::Directory,
+            'l' => FileKind::Symlink,
+            'p' => FileKind::Pipe,
+            's' => FileKind::Socket,
+            'b' => FileKind::BlockDevice,
+            'c' => FileKind::CharDevice,
+            _ => return None,
+        })
+    }
+    
+    pub fn letter(self) -> char {
+        match self {
+            FileKind::File => 'f',
+            FileKind::Directory => 'd',
+            FileKind::Symlink => 'l',
+            FileKind::Pipe => 'p',
+            FileKind::Socket => 's',
+            FileKind::BlockDevice => 'b',
+            FileKind::CharDevice => 'c',
+        }
+    }
+}
+
+/// Everything that defines a search. Built with [`SearchConfig::builder`].
+#[derive(Clone, Debug)]
+pub struct SearchConfig {
+    pub(crate) query: String,
+    pub(crate) alternatives: Vec<String>,
+    pub(crate) start_dirs: Vec<PathBuf>,
+    pub(crate) mode: SearchMode,
+    pub(crate) max_threads: usize,
+    pub(crate) use_wildcards: bool,
+    pub(crate) case_sensitive: bool,
+    pub(crate) smart_case: bool,
+    pub(crate) normalization: Normalization,
+    pub(crate) ignore_accents: bool,
+    pub(crate) fuzzy: bool,
+    pub(crate) match_target: MatchTarget,
+    pub(crate) extensions: Vec<String>,
+    pub(crate) expression: Option<Expression>,
+    pub(crate) file_kinds: Vec<FileKind>,
+    pub(crate) only_empty: bool,
+    pub(crate) only_executable: bool,
+    pub(crate) user: Option<u32>,
+    pub(crate) group: Option<u32>,
+    pub(crate) no_user: bool,
+    pub(crate) no_group: bool,
+    pub(crate) permissions: Option<PermissionFilter>,
+    pub(crate) mime_types: Vec<String>,
+    pub(crate) log_path: Option<PathBuf>,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) breadth_first: bool,
+    pub(crate) checkpoint_path: Option<PathBuf>,
+    pub(crate) checkpoint_interval: Duration,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) progress_interval: Duration,
+}
+
+impl SearchConfig {
+    /// Starts a configuration for `query`. Without a start directory only
+    /// paths given to [`SearchEngine::add_candidates`](crate::SearchEngine::add_candidates)
+    /// are matched.
+    pub fn builder(query: impl Into<String>) -> SearchConfigBuilder {
+        let num_cpus = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        
+        SearchConfigBuilder {
+            config: SearchConfig {
+                query: query.into(),
+                alternatives: Vec::new(),
+                start_dirs: Vec::new(),
+                mode: SearchMode::Both,
+                max_threads: num_cpus,
+                use_wildcards: true,
+                case_sensitive: true,
+                smart_case: false,
+                normalization: Normalization::None,
+                ignore_accents: false,
+                fuzzy: false,
+                match_target: MatchTarget::Name,
+                extensions: Vec::new(),
+                expression: None,
+                file_kinds: Vec::new(),
+                only_empty: false,
+                only_executable: false,
+                user: None,
+                group: None,
+                no_user: false,
+                no_group: false,
+                permissions: None,
+                mime_types: Vec::new(),
+                log_path: None,
+                max_depth: None,
+                breadth_first: true,
+                checkpoint_path: None,
+                checkpoint_interval: Duration::from_secs(30),
+                timeout: None,
+                progress_interval: Duration::from_secs(2),
+            },
+        }
+    }
+    
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+    
+    /// Other queries a name may match instead of the main one.
+    pub fn alternatives(&self) -> &[String] {
+        &self.alternatives
+    }
+    
+    /// The main query followed by its alternatives.
+    pub fn queries(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.query.as_str()).chain(self.alternatives.iter().map(String::as_str))
+    }
+    
+    pub fn start_dirs(&self) -> &[PathBuf] {
+        &self.start_dirs
+    }
+    
+    pub fn mode(&self) -> SearchMode {
+        self.mode
+    }
+    
+    pub fn max_threads(&self) -> usize {
+        self.max_threads
+    }
+    
+    pub fn is_fuzzy(&self) -> bool {
+        self.fuzzy
+    }
+    
+    pub fn match_target(&self) -> MatchTarget {
+        self.match_target
+    }
+    
+    /// Extensions a name must have one of; empty allows any.
+    pub fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+    
+    pub fn expression(&self) -> Option<&Expression> {
+        self.expression.as_ref()
+    }
+    
+    /// Kinds of entry that can match; empty allows any.
+    pub fn file_kinds(&self) -> &[FileKind] {
+        &self.file_kinds
+    }
+    
+    pub fn only_empty(&self) -> bool {
+        self.only_empty
+    }
+    
+    pub fn only_executable(&self) -> bool {
+        self.only_executable
+    }
+    
+    /// The user ID matches must be owned by.
+    pub fn user(&self) -> Option<u32> {
+        self.user
+    }
+    
+    /// The group ID matches must belong to.
+    pub fn group(&self) -> Option<u32> {
+        self.group
+    }
+    
+    pub fn no_user(&self) -> bool {
+        self.no_user
+    }
+    
+    pub fn no_group(&self) -> bool {
+        self.no_group
+    }
+    
+    pub fn permissions(&self) -> Option<PermissionFilter> {
+        self.permissions
+    }
+    
+    /// MIME type patterns a file's contents must fit one of; empty allows any.
+    pub fn mime_types(&self) -> &[String] {
+        &self.mime_types
+    }
+    
+    /// Whether `query` is matched case-sensitively; with smart case that
+    /// depends on whether it has uppercase letters.
+    pub fn case_sensitive_for(&self, query: &str) -> bool {
+        if self.smart_case {
+            has_uppercase(query)
+        } else {
+            self.case_sensitive
+        }
+    }
+    
+    pub fn normalization(&self) -> Normalization {
+        self.normalization
+    }
+    
+    pub fn ignores_accents(&self) -> bool {
+        self.ignore_accents
+    }
+    
+    pub fn log_path(&self) -> Option<&Path> {
+        self.log_path.as_deref()
+    }
+    
+    pub fn checkpoint_path(&self) -> Option<&Path> {
+        self.checkpoint_path.as_deref()
+    }
+    
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+    
+    /// The start directory `path` was found under. If one start directory
+    /// is inside another, paths below it belong to the inner one, which is
+    /// walked on its own.
+    pub fn root_of(&self, path: &Path) -> Option<&Path> {
+        self.start_dirs.iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .map(|root| root.as_path())
+    }
+}
+
+#[derive(Clone)]
+pub struct SearchConfigBuilder {
+    config: SearchConfig,
+}
+
+impl SearchConfigBuilder {
+    pub fn query(mut self, query: impl Into<String>) -> Self {
+        self.config.query = query.into();
+        self
+    }
+    
+    /// Also matches names that fit `query`.
+    pub fn also_match(mut self, query: impl Into<String>) -> Self {
+        self.config.alternatives.push(query.into());
+        self
+    }
+    
+    /// Replaces the alternative queries.
+    pub fn alternatives(mut self, queries: Vec<String>) -> Self {
+        self.config.alternatives = queries;
+        self
+    }
+    
+    pub fn mode(mut self, mode: SearchMode) -> Self {
+        self.config.mode = mode;
+        self
+    }
+    
+    /// Adds a directory to search from.
+    pub fn start_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.start_dirs.push(dir.into());
+        self
+    }
+    
+    /// Replaces the directories to search from.
+    pub fn start_dirs<I, P>(mut self, dirs: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.config.start_dirs = dirs.into_iter().map(Into::into).collect();
+        self
+    }
+    
+    /// Worker threads to use; capped at the number of CPUs.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.config.max_threads = threads.max(1);
+        self
+    }
+    
+    pub fn case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.config.case_sensitive = case_sensitive;
+        self
+    }
+    
+    /// Whether `*` and `?` in the query are wildcards rather than literal characters.
+    pub fn wildcards(mut self, use_wildcards: bool) -> Self {
+        self.config.use_wildcards = use_wildcards;
+        self
+    }
+    
+    /// Matches case-insensitively unless the query has an uppercase
+    /// letter, in which case it's matched exactly. Takes precedence over
+    /// [`case_sensitive`](Self::case_sensitive).
+    pub fn smart_case(mut self, smart_case: bool) -> Self {
+        self.config.smart_case = smart_case;
+        self
+    }
+    
+    /// Compares names and queries in this normalisation form, so names
+    /// written with combining accents match queries typed without them.
+    pub fn normalization(mut self, form: Normalization) -> Self {
+        self.config.normalization = form;
+        self
+    }
+    
+    /// Ignores accents and spells out Latin ligatures when comparing, so
+    /// `Zurich*` finds `Zürich-Bericht.pdf`. Works with every other mode.
+    pub fn ignore_accents(mut self, ignore_accents: bool) -> Self {
+        self.config.ignore_accents = ignore_accents;
+        self
+    }
+    
+    /// Matches names that contain the query's characters in order and
+    /// scores each one; see [`FuzzyPattern`](crate::FuzzyPattern).
+    /// Wildcards are ignored.
+    pub fn fuzzy(mut self, fuzzy: bool) -> Self {
+        self.config.fuzzy = fuzzy;
+        self
+    }
+    
+    /// Matches the query against this part of each path instead of the name.
+    pub fn match_on(mut self, target: MatchTarget) -> Self {
+        self.config.match_target = target;
+        self
+    }
+    
+    /// Only matches names ending in one of these extensions, compared
+    /// case-insensitively; `tar.gz` works too. A leading dot is optional.
+    pub fn extensions(mut self, extensions: Vec<String>) -> Self {
+        self.config.extensions = extensions.into_iter()
+            .map(|extension| extension.strip_prefix('.').map(str::to_string).unwrap_or(extension))
+            .collect();
+        self
+    }
+    
+    /// Matches with `expression` instead of the query and its alternatives,
+    /// which are then only used to describe the search.
+    pub fn expression(mut self, expression: Option<Expression>) -> Self {
+        self.config.expression = expression;
+        self
+    }
+    
+    /// Only matches entries of these kinds. This narrows the search mode
+    /// rather than replacing it: symlinks, pipes and the like count as
+    /// files there, so `SearchMode::Directory` only ever finds directories.
+    pub fn file_kinds(mut self, kinds: Vec<FileKind>) -> Self {
+        self.config.file_kinds = kinds;
+        self
+    }
+    
+    /// Only matches zero-byte files and directories with nothing in them.
+    pub fn only_empty(mut self, only_empty: bool) -> Self {
+        self.config.only_empty = only_empty;
+        self
+    }
+    
+    /// Only matches files that can be run: with an execute permission bit
+    /// set on Unix, or a program extension like `.exe` on Windows.
+    pub fn only_executable(mut self, only_executable: bool) -> Self {
+        self.config.only_executable = only_executable;
+        self
+    }
+    
+    /// Only matches entries owned by this user ID. Ownership is only known
+    /// on Unix; elsewhere nothing matches.
+    pub fn user(mut self, uid: Option<u32>) -> Self {
+        self.config.user = uid;
+        self
+    }
+    
+    /// Only matches entries whose group is this group ID (Unix only).
+    pub fn group(mut self, gid: Option<u32>) -> Self {
+        self.config.group = gid;
+        self
+    }
+    
+    /// Only matches entries owned by a user ID no account has, like files
+    /// left behind by a deleted user (Unix only).
+    pub fn no_user(mut self, no_user: bool) -> Self {
+        self.config.no_user = no_user;
+        self
+    }
+    
+    /// Only matches entries whose group ID no group has (Unix only).
+    pub fn no_group(mut self, no_group: bool) -> Self {
+        self.config.no_group = no_group;
+        self
+    }
+    
+    /// Only matches entries whose permission bits pass `filter` (Unix only).
+    pub fn permissions(mut self, filter: Option<PermissionFilter>) -> Self {
+        self.config.permissions = filter;
+        self
+    }
+    
+    /// Only matches files whose first bytes mark them as one of these MIME
+    /// types. Patterns may use wildcards, like `image/*`; see
+    /// [`detect_mime`](crate::detect_mime) for the types that are known.
+    pub fn mime_types(mut self, mime_types: Vec<String>) -> Self {
+        self.config.mime_types = mime_types;
+        self
+    }
+    
+    /// How many levels below each start directory to descend.
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.config.max_depth = max_depth;
+        self
+    }
+    
+    pub fn breadth_first(mut self, breadth_first: bool) -> Self {
+        self.config.breadth_first = breadth_first;
+        self
+    }
+    
+    /// Appends a session header, every match and a summary to this file.
+    pub fn log_path(mut self, log_path: Option<PathBuf>) -> Self {
+        self.config.log_path = log_path;
+        self
+    }
+    
+    /// Periodically saves progress here so the search can be resumed.
+    pub fn checkpoint_path(mut self, checkpoint_path: Option<PathBuf>) -> Self {
+        self.config.checkpoint_path = checkpoint_path;
+        self
+    }
+    
+    /// Stops the search once this much time has passed.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.config.timeout = timeout;
+        self
+    }
+    
+    /// How often the checkpoint file is rewritten while the search runs.
+    pub fn checkpoint_interval(mut self, interval: Duration) -> Self {
+        self.config.checkpoint_interval = interval;
+        self
+    }
+    
+    /// How often a [`SearchEvent::Progress`](crate::SearchEvent::Progress) is sent.
+    pub fn progress_interval(mut self, interval: Duration) -> Self {
+        self.config.progress_interval = interval;
+        self
+    }
+    
+    pub fn build(self) -> SearchConfig {
+        self.config
+    }
+}
@@ -0,0 +1,1175 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::checkpoint::Checkpoint;
+use crate::config::{FileKind, SearchConfig, SearchMode};
+use crate::escape::display_path;
+use crate::mime::detect_mime;
+use crate::pattern::PatternSet;
+use crate::platform::system_description;
+
+// Workers hand over matches in batches, but not so rarely that a caller
+// waiting on the first few is kept waiting
+const FLUSH_BATCH: usize = 100;
+const FLUSH_INTERVAL: Duration = Duration::from_millis(200);
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+// ==============================================
+// DATA STRUCTURES
+// ==============================================
+
+struct GlobalState {
+    found_items: HashSet<PathBuf>,
+    searched_dirs: HashSet<PathBuf>,
+    pending_dirs: VecDeque<PathBuf>,
+    // Directories whose matches are still buffered in a worker; a checkpoint
+    // treats them as pending so nothing is lost on resume
+    in_progress: HashSet<PathBuf>,
+    log_file: Option<fs::File>,
+    start_time: SystemTime,
+    dirs_searched: usize,
+    files_scanned: usize,
+    matches_found: usize,
+    io_errors: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StopReason {
+    Completed,
+    Cancelled,
+    TimedOut,
+    /// The caller had what it needed, e.g. declined the next page.
+    Stopped,
+}
+
+/// A file or directory whose name matched the query.
+#[derive(Clone, Debug)]
+pub struct Match {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    /// The start directory it was found under; `None` for listed paths.
+    pub root: Option<PathBuf>,
+    /// The worker that found it; `None` for listed paths.
+    pub thread_id: Option<usize>,
+    /// How well the name fit in a fuzzy search, higher being better.
+    pub score: Option<i32>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SearchErrorKind {
+    /// A directory couldn't be listed.
+    ReadDir,
+    /// An entry inside a directory couldn't be read.
+    ReadEntry,
+    /// A listed path couldn't be read.
+    ReadPath,
+    /// The log file couldn't be opened.
+    Log,
+    /// A checkpoint couldn't be written.
+    Checkpoint,
+}
+
+/// A problem the search ran into and carried on past.
+#[derive(Clone, Debug)]
+pub struct SearchError {
+    pub kind: SearchErrorKind,
+    pub path: PathBuf,
+    // Shared so every subscriber can get a copy of the event
+    pub error: Arc<io::Error>,
+    /// The worker that hit it, if it happened while walking.
+    pub thread_id: Option<usize>,
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = match self.kind {
+            SearchErrorKind::ReadDir => "read directory",
+            SearchErrorKind::ReadEntry => "read entry in",
+            SearchErrorKind::ReadPath => "read",
+            SearchErrorKind::Log => "open log file",
+            SearchErrorKind::Checkpoint => "write checkpoint",
+        };
+        write!(f, "Cannot {} '{}': {}", what, self.path.display(), self.error)
+    }
+}
+
+impl std::error::Error for SearchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.error.as_ref())
+    }
+}
+
+/// Counters for a search, live or finished.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SearchStats {
+    pub dirs_searched: usize,
+    pub dirs_remaining: usize,
+    pub files_scanned: usize,
+    pub matches_found: usize,
+    /// Entries that couldn't be read; a non-zero count means results may be incomplete.
+    pub io_errors: usize,
+    pub elapsed: Duration,
+}
+
+impl SearchStats {
+    pub fn files_per_sec(&self) -> f32 {
+        self.files_scanned as f32 / self.elapsed.as_secs_f32().max(0.1)
+    }
+}
+
+/// What a search reports as it runs, in order: `Started`, then any mix of
+/// the others, then `Finished` last.
+#[derive(Clone, Debug)]
+pub enum SearchEvent {
+    Started {
+        query: String,
+        start_dirs: Vec<PathBuf>,
+        threads: usize,
+        listed_paths: usize,
+    },
+    /// A worker began reading a directory.
+    DirEntered {
+        path: PathBuf,
+        thread_id: usize,
+    },
+    Match(Match),
+    Error(SearchError),
+    /// Sent every [`progress_interval`](crate::SearchConfigBuilder::progress_interval).
+    Progress {
+        dirs: usize,
+        files: usize,
+        matches: usize,
+        /// Files scanned per second.
+        rate: f32,
+    },
+    /// The search was cancelled, timed out or stopped and the workers are finishing up.
+    Stopping {
+        reason: StopReason,
+    },
+    Finished {
+        stats: SearchStats,
+        reason: StopReason,
+    },
+}
+
+// ==============================================
+// THREAD-SAFE SEARCH ENGINE (CROSS-PLATFORM)
+// ==============================================
+
+pub struct SearchEngine {
+    state: Arc<Mutex<GlobalState>>,
+    config: SearchConfig,
+    cancelled: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    stopped: Arc<AtomicBool>,
+    timed_out: AtomicBool,
+    // Settled when the search finishes, so a late cancel can't change it
+    final_reason: Option<StopReason>,
+    // Paths to match without walking, e.g. from --paths-from
+    candidates: Vec<PathBuf>,
+    subscribers: Vec<Sender<SearchEvent>>,
+}
+
+impl SearchEngine {
+    pub fn new(config: SearchConfig) -> Self {
+        let mut pending_dirs = VecDeque::new();
+        pending_dirs.extend(config.start_dirs.iter().cloned());
+        
+        let state = GlobalState {
+            found_items: HashSet::new(),
+            searched_dirs: HashSet::new(),
+            pending_dirs,
+            in_progress: HashSet::new(),
+            log_file: None,
+            start_time: SystemTime::now(),
+            dirs_searched: 0,
+            files_scanned: 0,
+            matches_found: 0,
+            io_errors: 0,
+        };
+        
+        Self {
+            state: Arc::new(Mutex::new(state)),
+            config,
+            cancelled: Arc::new(AtomicBool::new(false)),
+            paused: Arc::new(AtomicBool::new(false)),
+            stopped: Arc::new(AtomicBool::new(false)),
+            timed_out: AtomicBool::new(false),
+            final_reason: None,
+            candidates: Vec::new(),
+            subscribers: Vec::new(),
+        }
+    }
+    
+    pub fn config(&self) -> &SearchConfig {
+        &self.config
+    }
+    
+    /// Picks up a checkpointed search. The config should come from
+    /// [`Checkpoint::apply_to`] so the two describe the same search.
+    pub fn restore(&mut self, checkpoint: Checkpoint) {
+        let mut state = self.state.lock().unwrap();
+        state.pending_dirs = checkpoint.pending_dirs;
+        state.searched_dirs = checkpoint.searched_dirs;
+        state.found_items = checkpoint.found_items;
+        state.dirs_searched = checkpoint.dirs_searched;
+        state.files_scanned = checkpoint.files_scanned;
+        state.matches_found = checkpoint.matches_found;
+    }
+    
+    /// Adds paths to match as they are, without walking them.
+    pub fn add_candidates(&mut self, paths: Vec<PathBuf>) {
+        self.candidates.extend(paths);
+    }
+    
+    /// Returns a channel that receives every event of the next search.
+    /// It closes after `Finished`.
+    pub fn subscribe(&mut self) -> Receiver<SearchEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+    
+    /// The flag that stops the search when set, e.g. from a signal handler.
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+        self.cancelled.clone()
+    }
+    
+    /// Stops the search when `flag` is set instead of the engine's own flag,
+    /// for callers that need a flag before the engine exists.
+    pub fn set_cancel_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.cancelled = flag;
+    }
+    
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+    
+    pub fn stats(&self) -> SearchStats {
+        let state = self.state.lock().unwrap();
+        SearchStats {
+            dirs_searched: state.dirs_searched,
+            dirs_remaining: state.pending_dirs.len(),
+            files_scanned: state.files_scanned,
+            matches_found: state.matches_found,
+            io_errors: state.io_errors,
+            elapsed: state.start_time.elapsed().unwrap_or_default(),
+        }
+    }
+    
+    /// Everything found so far, including matches restored from a checkpoint.
+    pub fn found_items(&self) -> Vec<PathBuf> {
+        self.state.lock().unwrap().found_items.iter().cloned().collect()
+    }
+    
+    /// Drops `path`, and anything found inside it, from the results, e.g.
+    /// because it was deleted after being found. Returns how many matches
+    /// were dropped.
+    pub fn forget(&mut self, path: &Path) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let before = state.found_items.len();
+        state.found_items.retain(|found| !found.starts_with(path));
+        let dropped = before - state.found_items.len();
+        state.matches_found = state.matches_found.saturating_sub(dropped);
+        dropped
+    }
+    
+    pub fn stop_reason(&self) -> StopReason {
+        if let Some(reason) = self.final_reason {
+            reason
+        } else if self.timed_out.load(Ordering::SeqCst) {
+            StopReason::TimedOut
+        } else if self.stopped.load(Ordering::SeqCst) {
+            StopReason::Stopped
+        } else if self.cancelled.load(Ordering::SeqCst) {
+            StopReason::Cancelled
+        } else {
+            StopReason::Completed
+        }
+    }
+    
+    /// Runs the search to the end and returns every match.
+    pub fn search(&mut self) -> Vec<PathBuf> {
+        self.state.lock().unwrap().start_time = SystemTime::now();
+        self.final_reason = None;
+        
+        let log_path_clone = self.config.log_path.clone();
+        if let Some(ref log_path) = log_path_clone {
+            if let Err(error) = self.setup_logging(log_path) {
+                self.emit(SearchEvent::Error(SearchError {
+                    kind: SearchErrorKind::Log,
+                    path: log_path.clone(),
+                    error: Arc::new(error),
+                    thread_id: None,
+                }));
+            }
+        }
+        
+        let num_cpus = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        let thread_count = self.config.max_threads.min(num_cpus);
+        
+        self.emit(SearchEvent::Started {
+            query: self.config.query.clone(),
+            start_dirs: self.config.start_dirs.clone(),
+            threads: thread_count,
+            listed_paths: self.candidates.len(),
+        });
+        
+        let mut handles = vec![];
+        let pattern = Arc::new(PatternSet::new(&self.config));
+        
+        self.filter_candidates(&pattern);
+        
+        // Workers report to this thread, which passes events on to subscribers
+        let (sender, receiver) = mpsc::channel();
+        let report_dirs = !self.subscribers.is_empty();
+        
+        for thread_id in 0..thread_count {
+            let state_clone = self.state.clone();
+            let pattern_clone = pattern.clone();
+            let config_clone = self.config.clone();
+            let controls = Controls {
+                cancelled: self.cancelled.clone(),
+                paused: self.paused.clone(),
+            };
+            let sender_clone = sender.clone();
+            
+            let handle = thread::spawn(move || {
+                Self::worker_thread(thread_id, state_clone, pattern_clone, config_clone, controls, sender_clone, report_dirs);
+            });
+            
+            handles.push(handle);
+        }
+        drop(sender);
+        
+        self.monitor_progress(&receiver);
+        
+        for handle in handles {
+            let _ = handle.join();
+        }
+        
+        // Workers flush their buffered matches on the way out
+        for event in receiver.try_iter() {
+            self.emit(event);
+        }
+        
+        if let Some(ref checkpoint_path) = self.config.checkpoint_path {
+            if let Err(error) = self.write_checkpoint(checkpoint_path) {
+                self.emit(SearchEvent::Error(SearchError {
+                    kind: SearchErrorKind::Checkpoint,
+                    path: checkpoint_path.clone(),
+                    error: Arc::new(error),
+                    thread_id: None,
+                }));
+            }
+        }
+        
+        // The log summary is only accurate once every thread has been joined
+        self.write_log_summary();
+        
+        let reason = self.stop_reason();
+        self.final_reason = Some(reason);
+        self.emit(SearchEvent::Finished {
+            stats: self.stats(),
+            reason,
+        });
+        // Dropping the senders closes every subscriber's channel
+        self.subscribers.clear();
+        
+        self.found_items()
+    }
+    
+    /// Runs the search on a background thread; subscribe first to follow it.
+    pub fn spawn(self) -> SearchHandle {
+        let cancelled = self.cancel_flag();
+        let paused = self.paused.clone();
+        let stopped = self.stopped.clone();
+        let handle = thread::spawn(move || {
+            let mut engine = self;
+            engine.search();
+            engine
+        });
+        
+        SearchHandle { cancelled, paused, stopped, handle }
+    }
+    
+    /// Runs the search on a background thread and yields matches as they
+    /// are found. Dropping the iterator cancels the search.
+    pub fn into_results(mut self) -> Results {
+        let events = self.subscribe();
+        
+        Results {
+            events,
+            search: Some(self.spawn()),
+        }
+    }
+    
+    fn emit(&self, event: SearchEvent) {
+        for subscriber in &self.subscribers {
+            let _ = subscriber.send(event.clone());
+        }
+    }
+    
+    fn worker_thread(thread_id: usize, state: Arc<Mutex<GlobalState>>, pattern: Arc<PatternSet>, config: SearchConfig, controls: Controls, events: Sender<SearchEvent>, report_dirs: bool) {
+        let cancelled = controls.cancelled;
+        let mut local_found = Vec::new();
+        let mut local_dirs = Vec::new();
+        let mut last_flush = Instant::now();
+        
+        loop {
+            // Stop picking up new work once cancelled; anything buffered
+            // in local_found is flushed below
+            if cancelled.load(Ordering::Relaxed) {
+                break;
+            }
+            
+            // A paused search holds on to its queue; everything found up to
+            // now is handed over first so the caller's page is complete
+            if controls.paused.load(Ordering::Relaxed) {
+                Self::flush_found(&state, &mut local_found, &mut local_dirs, &events);
+                while controls.paused.load(Ordering::Relaxed) && !cancelled.load(Ordering::Relaxed) {
+                    thread::sleep(PAUSE_POLL_INTERVAL);
+                }
+                last_flush = Instant::now();
+                continue;
+            }
+            
+            let next_dir = {
+                let mut state_lock = state.lock().unwrap();
+                
+                if state_lock.pending_dirs.is_empty() {
+                    break;
+                }
+                
+                state_lock.pending_dirs.pop_front()
+            };
+            
+            let dir = match next_dir {
+                Some(dir) => dir,
+                None => continue,
+            };
+            
+            {
+                let mut state_lock = state.lock().unwrap();
+                if state_lock.searched_dirs.contains(&dir) {
+                    continue;
+                }
+                state_lock.searched_dirs.insert(dir.clone());
+                state_lock.in_progress.insert(dir.clone());
+                state_lock.dirs_searched += 1;
+            }
+            
+            if report_dirs {
+                let _ = events.send(SearchEvent::DirEntered { path: dir.clone(), thread_id });
+            }
+            
+            // Cross-platform directory reading with error handling
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(error) => {
+                    // A directory that vanished mid-search doesn't make the results partial
+                    if error.kind() != io::ErrorKind::NotFound {
+                        state.lock().unwrap().io_errors += 1;
+                    }
+                    
+                    let _ = events.send(SearchEvent::Error(SearchError {
+                        kind: SearchErrorKind::ReadDir,
+                        path: dir.clone(),
+                        error: Arc::new(error),
+                        thread_id: Some(thread_id),
+                    }));
+                    local_dirs.push(dir);
+                    continue;
+                }
+            };
+            
+            let root = config.root_of(&dir).unwrap_or(&dir).to_path_buf();
+            
+            let mut subdirs = Vec::new();
+            let mut interrupted = false;
+            
+            for entry in entries {
+                if cancelled.load(Ordering::Relaxed) {
+                    interrupted = true;
+                    break;
+                }
+                
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(error) => {
+                        state.lock().unwrap().io_errors += 1;
+                        let _ = events.send(SearchEvent::Error(SearchError {
+                            kind: SearchErrorKind::ReadEntry,
+                            path: dir.clone(),
+                            error: Arc::new(error),
+                            thread_id: Some(thread_id),
+                        }));
+                        continue;
+                    }
+                };
+                
+                let path = entry.path();
+                
+                // Cross-platform file type detection with fallback
+                let file_type = match entry.file_type() {
+                    Ok(file_type) => file_type,
+                    Err(_) => {
+                        // Fallback: try metadata for Windows compatibility
+                        if let Ok(metadata) = fs::metadata(&path) {
+                            metadata.file_type()
+                        } else {
+                            continue;
+                        }
+                    }
+                };
+                let is_dir = file_type.is_dir();
+                let kind = FileKind::of(&file_type);
+                
+                let is_file = !is_dir;
+                
+                let name = entry.file_name();
+                
+                if name == "." || name == ".." {
+                    continue;
+                }
+                
+                {
+                    let mut state_lock = state.lock().unwrap();
+                    state_lock.files_scanned += 1;
+                }
+                
+                if is_dir {
+                    if config.mode != SearchMode::File {
+                        if let Some(score) = pattern.check(&path, Some(&root), kind) {
+                            local_found.push(Match {
+                                path: path.clone(),
+                                is_dir,
+                                root: Some(root.clone()),
+                                thread_id: Some(thread_id),
+                                score,
+                            });
+                        }
+                    }
+                    
+                    // A start directory inside this one has a walk of its
+                    // own, which counts the depth from there
+                    let own_walk = config.start_dirs.contains(&path);
+                    let too_deep = config.max_depth
+                        .is_some_and(|max_depth| Self::calculate_depth(&path, &root) > max_depth);
+                    if !own_walk && !too_deep {
+                        subdirs.push(path);
+                    }
+                
+                } else if is_file && config.mode != SearchMode::Directory {
+                    if let Some(score) = pattern.check(&path, Some(&root), kind) {
+                        local_found.push(Match {
+                            path,
+                            is_dir,
+                            root: Some(root.clone()),
+                            thread_id: Some(thread_id),
+                            score,
+                        });
+                    }
+                }
+            }
+            
+            if interrupted {
+                // Hand the half-read directory back so a resumed search
+                // reads it again from the start
+                let mut state_lock = state.lock().unwrap();
+                state_lock.searched_dirs.remove(&dir);
+                state_lock.in_progress.remove(&dir);
+                state_lock.dirs_searched -= 1;
+                state_lock.pending_dirs.push_front(dir);
+                break;
+            }
+            
+            local_dirs.push(dir);
+            
+            {
+                let mut state_lock = state.lock().unwrap();
+                if config.breadth_first {
+                    state_lock.pending_dirs.extend(subdirs);
+                } else {
+                    for subdir in subdirs.into_iter().rev() {
+                        state_lock.pending_dirs.push_front(subdir);
+                    }
+                }
+                // With no matches held back, the directories read so far are
+                // done; a checkpoint must not hand them out again
+                if local_found.is_empty() {
+                    for dir in local_dirs.drain(..) {
+                        state_lock.in_progress.remove(&dir);
+                    }
+                }
+            }
+            
+            if local_found.len() >= FLUSH_BATCH
+                || (!local_found.is_empty() && last_flush.elapsed() >= FLUSH_INTERVAL)
+            {
+                Self::flush_found(&state, &mut local_found, &mut local_dirs, &events);
+                last_flush = Instant::now();
+            }
+        }
+        
+        Self::flush_found(&state, &mut local_found, &mut local_dirs, &events);
+    }
+    
+    fn flush_found(state: &Mutex<GlobalState>, local_found: &mut Vec<Match>, local_dirs: &mut Vec<PathBuf>, events: &Sender<SearchEvent>) {
+        let mut state_lock = state.lock().unwrap();
+        for found in local_found.drain(..) {
+            if !state_lock.found_items.insert(found.path.clone()) {
+                continue;
+            }
+            state_lock.matches_found += 1;
+            
+            if let Some(ref mut log_file) = state_lock.log_file {
+                Self::write_log_line(log_file, &Self::describe(&found));
+            }
+            let _ = events.send(SearchEvent::Match(found));
+        }
+        for dir in local_dirs.drain(..) {
+            state_lock.in_progress.remove(&dir);
+        }
+    }
+    
+    fn describe(found: &Match) -> String {
+        let kind = if found.is_dir { "directory" } else { "file" };
+        format!("Found {}: {}", kind, display_path(&found.path))
+    }
+    
+    fn write_log_line(log_file: &mut fs::File, message: &str) {
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| {
+                let secs = d.as_secs();
+                format!("{:02}:{:02}:{:02}",
+                    (secs / 3600) % 24,
+                    (secs / 60) % 60,
+                    secs % 60)
+            })
+            .unwrap_or_else(|_| "00:00:00".to_string());
+        
+        let _ = writeln!(log_file, "[{}] {}", timestamp, message);
+    }
+    
+    /// Matches paths handed to us directly instead of found by walking.
+    /// Only the name and type are checked.
+    fn filter_candidates(&self, pattern: &PatternSet) {
+        for path in &self.candidates {
+            if self.cancelled.load(Ordering::Relaxed) {
+                break;
+            }
+            
+            let file_type = match fs::symlink_metadata(path) {
+                Ok(metadata) => metadata.file_type(),
+                Err(error) => {
+                    if error.kind() != io::ErrorKind::NotFound {
+                        self.state.lock().unwrap().io_errors += 1;
+                    }
+                    self.emit(SearchEvent::Error(SearchError {
+                        kind: SearchErrorKind::ReadPath,
+                        path: path.clone(),
+                        error: Arc::new(error),
+                        thread_id: None,
+                    }));
+                    continue;
+                }
+            };
+            let is_dir = file_type.is_dir();
+            let kind = FileKind::of(&file_type);
+            
+            let found = {
+                let mut state = self.state.lock().unwrap();
+                state.files_scanned += 1;
+                
+                if path.file_name().is_none() {
+                    continue;
+                }
+                
+                let wanted = match self.config.mode {
+                    SearchMode::File => !is_dir,
+                    SearchMode::Directory => is_dir,
+                    SearchMode::Both => true,
+                };
+                if !wanted {
+                    continue;
+                }
+                let score = match pattern.check(path, None, kind) {
+                    Some(score) => score,
+                    None => continue,
+                };
+                
+                if !state.found_items.insert(path.clone()) {
+                    continue;
+                }
+                state.matches_found += 1;
+                
+                let found = Match {
+                    path: path.clone(),
+                    is_dir,
+                    root: None,
+                    thread_id: None,
+                    score,
+                };
+                if let Some(ref mut log_file) = state.log_file {
+                    Self::write_log_line(log_file, &Self::describe(&found));
+                }
+                found
+            };
+            
+            self.emit(SearchEvent::Match(found));
+        }
+    }
+    
+    /// Number of levels `path` sits below `start_dir`; its direct children are at depth 1.
+    fn calculate_depth(path: &Path, start_dir: &Path) -> usize {
+        let mut depth = 0;
+        let mut current = path;
+        
+        while let Some(parent) = current.parent() {
+            depth += 1;
+            if parent == start_dir {
+                break;
+            }
+            current = parent;
+            
+            // Handle Windows drive roots
+            if cfg!(windows) && parent.components().count() == 1 {
+                break;
+            }
+        }
+        
+        depth
+    }
+    
+    fn setup_logging(&mut self, log_path: &Path) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path)?;
+        
+        writeln!(file, "{}", "=".repeat(80)).ok();
+        writeln!(file, "FileSearch Session").ok();
+        writeln!(file, "System: {}", system_description()).ok();
+        writeln!(file, "Started: {:?}", SystemTime::now()).ok();
+        writeln!(file, "Query: {}", self.config.query).ok();
+        for alternative in &self.config.alternatives {
+            writeln!(file, "Or: {}", alternative).ok();
+        }
+        for start_dir in &self.config.start_dirs {
+            writeln!(file, "Directory: {}", start_dir.display()).ok();
+        }
+        writeln!(file, "{}", "=".repeat(80)).ok();
+        
+        state.log_file = Some(file);
+        Ok(())
+    }
+    
+    fn monitor_progress(&self, events: &Receiver<SearchEvent>) {
+        let start = Instant::now();
+        let mut last_progress = Instant::now();
+        let mut last_checkpoint = Instant::now();
+        // Time spent paused, e.g. while a page is read, doesn't count towards the timeout
+        let mut paused_for = Duration::ZERO;
+        let mut paused_since = None;
+        
+        loop {
+            // Waiting on the channel doubles as the poll interval; it
+            // disconnects once every worker has run out of directories
+            let workers_done = match events.recv_timeout(Duration::from_millis(100)) {
+                Ok(event) => {
+                    self.emit(event);
+                    for event in events.try_iter() {
+                        self.emit(event);
+                    }
+                    false
+                }
+                Err(RecvTimeoutError::Timeout) => false,
+                Err(RecvTimeoutError::Disconnected) => true,
+            };
+            
+            match (self.paused.load(Ordering::SeqCst), paused_since) {
+                (true, None) => paused_since = Some(Instant::now()),
+                (false, Some(since)) => {
+                    paused_for += since.elapsed();
+                    paused_since = None;
+                }
+                _ => {}
+            }
+            
+            if let Some(timeout) = self.config.timeout {
+                let paused_now = paused_since.map_or(Duration::ZERO, |since| since.elapsed());
+                if start.elapsed().saturating_sub(paused_for + paused_now) >= timeout {
+                    self.timed_out.store(true, Ordering::SeqCst);
+                    self.cancelled.store(true, Ordering::SeqCst);
+                    self.emit(SearchEvent::Stopping { reason: StopReason::TimedOut });
+                    break;
+                }
+            }
+            
+            if let Some(ref checkpoint_path) = self.config.checkpoint_path {
+                if last_checkpoint.elapsed() >= self.config.checkpoint_interval {
+                    if let Err(error) = self.write_checkpoint(checkpoint_path) {
+                        self.emit(SearchEvent::Error(SearchError {
+                            kind: SearchErrorKind::Checkpoint,
+                            path: checkpoint_path.clone(),
+                            error: Arc::new(error),
+                            thread_id: None,
+                        }));
+                    }
+                    last_checkpoint = Instant::now();
+                }
+            }
+            
+            if self.cancelled.load(Ordering::SeqCst) {
+                self.emit(SearchEvent::Stopping { reason: self.stop_reason() });
+                break;
+            }
+            
+            if workers_done {
+                break;
+            }
+            
+            if last_progress.elapsed() > self.config.progress_interval {
+                let stats = self.stats();
+                self.emit(SearchEvent::Progress {
+                    dirs: stats.dirs_searched,
+                    files: stats.files_scanned,
+                    matches: stats.matches_found,
+                    rate: stats.files_per_sec(),
+                });
+                last_progress = Instant::now();
+            }
+        }
+    }
+    
+    fn write_checkpoint(&self, path: &Path) -> io::Result<()> {
+        // Take a snapshot so the workers aren't held up while it's written
+        let checkpoint = {
+            let state = self.state.lock().unwrap();
+            
+            let mut pending_dirs: VecDeque<PathBuf> = state.in_progress.iter().cloned().collect();
+            pending_dirs.extend(state.pending_dirs.iter().cloned());
+            
+            Checkpoint {
+                mode: self.config.mode,
+                query: self.config.query.clone(),
+                alternatives: self.config.alternatives.clone(),
+                start_dirs: self.config.start_dirs.clone(),
+                case_sensitive: self.config.case_sensitive,
+                smart_case: self.config.smart_case,
+                normalization: self.config.normalization,
+                ignore_accents: self.config.ignore_accents,
+                use_wildcards: self.config.use_wildcards,
+                fuzzy: self.config.fuzzy,
+                match_target: self.config.match_target,
+                extensions: self.config.extensions.clone(),
+                file_kinds: self.config.file_kinds.clone(),
+                only_empty: self.config.only_empty,
+                only_executable: self.config.only_executable,
+                user: self.config.user,
+                group: self.config.group,
+                no_user: self.config.no_user,
+                no_group: self.config.no_group,
+                permissions: self.config.permissions,
+                mime_types: self.config.mime_types.clone(),
+                expression: self.config.expression.clone(),
+                max_depth: self.config.max_depth,
+                breadth_first: self.config.breadth_first,
+                pending_dirs,
+                searched_dirs: state.searched_dirs.difference(&state.in_progress).cloned().collect(),
+                found_items: state.found_items.clone(),
+                dirs_searched: state.dirs_searched - state.in_progress.len(),
+                files_scanned: state.files_scanned,
+                matches_found: state.matches_found,
+            }
+        };
+        
+        checkpoint.save(path)
+    }
+    
+    fn write_log_summary(&self) {
+        let stats = self.stats();
+        let stop_reason = self.stop_reason();
+        let mut state = self.state.lock().unwrap();
+        
+        if let Some(ref mut log_file) = state.log_file {
+            let seconds = stats.elapsed.as_secs_f32();
+            let headline = match stop_reason {
+                StopReason::Completed => format!("Search completed in {:.2} seconds", seconds),
+                StopReason::Cancelled => format!("Search cancelled after {:.2} seconds", seconds),
+                StopReason::TimedOut => format!("Search timed out after {:.2} seconds", seconds),
+                StopReason::Stopped => format!("Search stopped after {:.2} seconds", seconds),
+            };
+            
+            writeln!(log_file, "\n{}", "=".repeat(80)).ok();
+            writeln!(log_file, "{}", headline).ok();
+            writeln!(log_file, "Directories searched: {}", stats.dirs_searched).ok();
+            if stop_reason != StopReason::Completed {
+                writeln!(log_file, "Directories remaining: {}", stats.dirs_remaining).ok();
+            }
+            writeln!(log_file, "Files scanned: {}", stats.files_scanned).ok();
+            writeln!(log_file, "Matches found: {}", stats.matches_found).ok();
+            if stats.io_errors > 0 {
+                writeln!(log_file, "Unreadable entries: {}", stats.io_errors).ok();
+            }
+            writeln!(log_file, "{}", "=".repeat(80)).ok();
+        }
+    }
+    
+    /// Writes a report of every match, with sizes and detected file types,
+    /// grouped by start directory.
+    pub fn save_results(&self, save_path: &Path) -> io::Result<()> {
+        let state = self.state.lock().unwrap();
+        
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(save_path)?;
+        
+        writeln!(file, "FileSearch Results")?;
+        writeln!(file, "System: {}", system_description())?;
+        writeln!(file, "Generated: {:?}", SystemTime::now())?;
+        writeln!(file, "Query: {}", self.config.query)?;
+        for alternative in &self.config.alternatives {
+            writeln!(file, "Or: {}", alternative)?;
+        }
+        for start_dir in &self.config.start_dirs {
+            writeln!(file, "Search directory: {}", start_dir.display())?;
+        }
+        writeln!(file, "{}", "-".repeat(80))?;
+        
+        let mut sorted_paths: Vec<_> = state.found_items.iter().collect();
+        sorted_paths.sort();
+        
+        // Results are grouped under the root they were found in
+        for start_dir in &self.config.start_dirs {
+            if self.config.start_dirs.len() > 1 {
+                writeln!(file, "\n{}:", start_dir.display())?;
+            }
+            
+            for path in sorted_paths.iter().filter(|path| self.config.root_of(path) == Some(start_dir)) {
+                Self::write_result_line(&mut file, path)?;
+            }
+        }
+        
+        // Anything outside every root was a listed path
+        let listed: Vec<_> = sorted_paths.iter().filter(|path| self.config.root_of(path).is_none()).collect();
+        if !listed.is_empty() {
+            if !self.config.start_dirs.is_empty() {
+                writeln!(file, "\nListed paths:")?;
+            }
+            for path in listed {
+                Self::write_result_line(&mut file, path)?;
+            }
+        }
+        
+        writeln!(file, "{}", "=".repeat(80))?;
+        writeln!(file, "Summary:")?;
+        writeln!(file, "  Total matches: {}", state.matches_found)?;
+        writeln!(file, "  Directories searched: {}", state.dirs_searched)?;
+        writeln!(file, "  Files scanned: {}", state.files_scanned)?;
+        match self.stop_reason() {
+            StopReason::Completed => {}
+            StopReason::Cancelled => writeln!(file, "  Note: search was cancelled, results are partial")?,
+            StopReason::TimedOut => writeln!(file, "  Note: search timed out, results are partial")?,
+            StopReason::Stopped => writeln!(file, "  Note: search was stopped early, results are partial")?,
+        }
+        
+        Ok(())
+    }
+    
+    fn write_result_line(file: &mut fs::File, path: &Path) -> io::Result<()> {
+        let size_info = match fs::metadata(path) {
+            Ok(md) if md.is_file() => match detect_mime(path) {
+                Some(mime) => format!(" ({}, {})", Self::human_readable_size(md.len()), mime),
+                None => format!(" ({})", Self::human_readable_size(md.len())),
+            },
+            Ok(md) => format!(" ({})", Self::human_readable_size(md.len())),
+            Err(_) => String::new(),
+        };
+        
+        let item_type = if path.is_dir() { "[DIR] " } else { "[FILE]" };
+        
+        writeln!(file, "{} {}{}", item_type, display_path(path), size_info)
+    }
+    
+    fn human_readable_size(bytes: u64) -> String {
+        const UNITS: [&str; 6] = ["B", "KB", "MB", "GB", "TB", "PB"];
+        
+        let mut size = bytes as f64;
+        let mut unit_idx = 0;
+        
+        while size >= 1024.0 && unit_idx < UNITS.len() - 1 {
+            size /= 1024.0;
+            unit_idx += 1;
+        }
+        
+        format!("{:.1} {}", size, UNITS[unit_idx])
+    }
+}
+
+/// A search running on a background thread, from [`SearchEngine::spawn`].
+pub struct SearchHandle {
+    cancelled: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    stopped: Arc<AtomicBool>,
+    handle: JoinHandle<SearchEngine>,
+}
+
+impl SearchHandle {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+    
+    /// Stops the workers from starting on new directories until
+    /// [`resume`](Self::resume), without giving up their place. The
+    /// directories being read finish first, so a few more matches may
+    /// still arrive. Time spent paused doesn't count towards the timeout.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+    
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+    
+    /// Ends the search early because the caller has what it needs. It
+    /// winds down like a cancel, but finishes as [`StopReason::Stopped`].
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.cancel();
+    }
+    
+    /// Waits for the search to finish and hands back the engine, for its
+    /// stats, stop reason or a saved report. A paused search is resumed.
+    pub fn join(self) -> SearchEngine {
+        self.resume();
+        match self.handle.join() {
+            Ok(engine) => engine,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+}
+
+/// Matches from [`SearchEngine::into_results`], in the order they're found.
+pub struct Results {
+    events: Receiver<SearchEvent>,
+    search: Option<SearchHandle>,
+}
+
+impl Results {
+    /// Stops the search; matches already found are still yielded.
+    pub fn cancel(&self) {
+        if let Some(ref search) = self.search {
+            search.cancel();
+        }
+    }
+    
+    /// See [`SearchHandle::pause`].
+    pub fn pause(&self) {
+        if let Some(ref search) = self.search {
+            search.pause();
+        }
+    }
+    
+    pub fn resume(&self) {
+        if let Some(ref search) = self.search {
+            search.resume();
+        }
+    }
+    
+    /// See [`SearchHandle::stop`].
+    pub fn stop(&self) {
+        if let Some(ref search) = self.search {
+            search.stop();
+        }
+    }
+    
+    /// Runs the search until `size` more matches are in, then pauses it
+    /// until the next page is asked for. A page shorter than `size` means
+    /// the search is over.
+    pub fn next_page(&mut self, size: usize) -> Vec<Match> {
+        self.next_page_with(size, |_| {})
+    }
+    
+    /// Like [`next_page`](Self::next_page), but every event taken off the
+    /// queue, the page's matches included, is handed to `on_event` first,
+    /// for callers that also report progress and errors.
+    pub fn next_page_with(&mut self, size: usize, mut on_event: impl FnMut(&SearchEvent)) -> Vec<Match> {
+        let mut page = Vec::new();
+        let mut take = |event: SearchEvent, page: &mut Vec<Match>| {
+            on_event(&event);
+            if let SearchEvent::Match(found) = event {
+                page.push(found);
+            }
+        };
+        
+        // Events that came in after the last page was paused go first
+        while page.len() < size {
+            match self.events.try_recv() {
+                Ok(event) => take(event, &mut page),
+                Err(_) => break,
+            }
+        }
+        
+        if page.len() < size {
+            self.resume();
+            while page.len() < size {
+                match self.events.recv() {
+                    Ok(event) => take(event, &mut page),
+                    Err(_) => break,
+                }
+            }
+        }
+        self.pause();
+        
+        page
+    }
+    
+    /// Waits for the search to finish and hands back the engine.
+    pub fn finish(mut self) -> SearchEngine {
+        self.search.take().expect("search already joined").join()
+    }
+}
+
+impl Iterator for Results {
+    type Item = Match;
+    
+    fn next(&mut self) -> Option<Match> {
+        loop {
+            if let SearchEvent::Match(found) = self.events.recv().ok()? {
+                return Some(found);
+            }
+        }
+    }
+}
+
+impl Drop for Results {
+    fn drop(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.cancel();
+            search.join();
+        }
+    }
+}
+
+// What a caller can do to running workers
+struct Controls {
+    cancelled: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+}
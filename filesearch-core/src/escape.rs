@@ -0,0 +1,194 @@
+// Paths are written one per line; backslashes, line breaks and bytes that
+// aren't valid UTF-8 are escaped so any file name survives the round trip.
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+pub fn escape_text(bytes: &[u8]) -> String {
+    let mut escaped = String::with_capacity(bytes.len());
+    
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                _ => escaped.push(c),
+            }
+        }
+        for byte in chunk.invalid() {
+            escaped.push_str(&format!("\\x{:02x}", byte));
+        }
+    }
+    
+    escaped
+}
+
+pub fn unescape_text(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut chars = text.chars();
+    
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        
+        match chars.next()? {
+            '\\' => bytes.push(b'\\'),
+            'n' => bytes.push(b'\n'),
+            'r' => bytes.push(b'\r'),
+            'x' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                bytes.push(u8::from_str_radix(&hex, 16).ok()?);
+            }
+            _ => return None,
+        }
+    }
+    
+    Some(bytes)
+}
+
+pub fn encode_path(path: &Path) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        escape_text(path.as_os_str().as_bytes())
+    }
+    
+    #[cfg(not(unix))]
+    {
+        escape_text(path.to_string_lossy().as_bytes())
+    }
+}
+
+pub fn decode_path(text: &str) -> Option<PathBuf> {
+    let bytes = unescape_text(text)?;
+    
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        Some(PathBuf::from(std::ffi::OsString::from_vec(bytes)))
+    }
+    
+    #[cfg(not(unix))]
+    {
+        String::from_utf8(bytes).ok().map(PathBuf::from)
+    }
+}
+
+/// A query taken apart at its wildcards.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum QueryPart {
+    Literal(Vec<u8>),
+    // `*`
+    AnyRun,
+    // `?`
+    AnyOne,
+}
+
+/// Splits a query into literal bytes and wildcards, turning the escapes
+/// into what they stand for: `\xNN` is a byte, so names that aren't valid
+/// UTF-8 can be asked for, and `\*` and `\?` are a plain `*` and `?`. Any
+/// other backslash is kept as it is, so `C:\\dir` means what it says; a
+/// backslash right before a wildcard is written `\\x5c`.
+/// Without `use_wildcards`, `*` and `?` are plain characters too.
+pub(crate) fn parse_query(query: &str, use_wildcards: bool) -> Vec<QueryPart> {
+    let mut parts = Vec::new();
+    let mut literal = Vec::with_capacity(query.len());
+    let mut rest = query;
+    
+    while let Some(index) = rest.find(['\\', '*', '?']) {
+        literal.extend_from_slice(&rest.as_bytes()[..index]);
+        let special = &rest[index..];
+        
+        let hex = special.get(2..4).filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()));
+        match special.as_bytes()[0] {
+            b'\\' => match hex {
+                Some(hex) if special.starts_with("\\x") => {
+                    literal.push(u8::from_str_radix(hex, 16).unwrap_or_default());
+                    rest = &special[4..];
+                }
+                _ if special.starts_with("\\*") || special.starts_with("\\?") => {
+                    literal.push(special.as_bytes()[1]);
+                    rest = &special[2..];
+                }
+                _ => {
+                    literal.push(b'\\');
+                    rest = &special[1..];
+                }
+            },
+            wildcard if use_wildcards => {
+                if !literal.is_empty() {
+                    parts.push(QueryPart::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(if wildcard == b'*' { QueryPart::AnyRun } else { QueryPart::AnyOne });
+                rest = &special[1..];
+            }
+            plain => {
+                literal.push(plain);
+                rest = &special[1..];
+            }
+        }
+    }
+    literal.extend_from_slice(rest.as_bytes());
+    if !literal.is_empty() || parts.is_empty() {
+        parts.push(QueryPart::Literal(literal));
+    }
+    
+    parts
+}
+
+/// The bytes a query stands for when `*` and `?` aren't wildcards.
+pub(crate) fn unescape_query(query: &str) -> Vec<u8> {
+    parse_query(query, false).into_iter()
+        .flat_map(|part| match part {
+            QueryPart::Literal(bytes) => bytes,
+            QueryPart::AnyRun | QueryPart::AnyOne => Vec::new(),
+        })
+        .collect()
+}
+
+/// A path as it should be shown to a person. On Unix, names that aren't
+/// valid UTF-8 or that hold control characters would otherwise print as
+/// replacement characters or garble the terminal, so those bytes are
+/// written as `\xNN`, the same syntax queries use, and so are
+/// backslashes, which a query would otherwise keep as they are. Other
+/// paths are shown as they are.
+pub fn display_path(path: &Path) -> Cow<'_, str> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let bytes = path.as_os_str().as_bytes();
+        
+        if let Ok(text) = std::str::from_utf8(bytes) {
+            if !text.chars().any(|c| c == '\\' || c.is_control()) {
+                return Cow::Borrowed(text);
+            }
+        }
+        
+        let mut shown = String::with_capacity(bytes.len());
+        for chunk in bytes.utf8_chunks() {
+            for c in chunk.valid().chars() {
+                if c == '\\' || c.is_control() {
+                    let mut buf = [0; 4];
+                    for byte in c.encode_utf8(&mut buf).bytes() {
+                        shown.push_str(&format!("\\x{:02x}", byte));
+                    }
+                } else {
+                    shown.push(c);
+                }
+            }
+            for byte in chunk.invalid() {
+                shown.push_str(&format!("\\x{:02x}", byte));
+            }
+        }
+        Cow::Owned(shown)
+    }
+    
+    #[cfg(not(unix))]
+    {
+        path.to_string_lossy()
+    }
+}
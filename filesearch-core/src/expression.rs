@@ -0,0 +1,833 @@
+use std::cell::OnceCell;
+use std::fmt;
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use crate::config::{FileKind, MatchTarget, SearchConfig};
+use crate::mime::{detect_mime, mime_for_kind};
+use crate::pattern::Pattern;
+use crate::permissions::PermissionFilter;
+
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+
+// How much of a file is read at a time when looking inside it
+const CONTENT_CHUNK: usize = 64 * 1024;
+
+/// A query written as an expression over names, metadata and contents:
+///
+/// ```text
+/// name:*.log AND size>10M AND (mtime<7d OR owner:www-data) AND NOT path:*/archive/*
+/// ```
+///
+/// Terms are `name:`, `path:`, `relpath:`, `stem:` and `ext:` patterns,
+/// `type:` (`find -type` letters), `size` and `mtime` comparisons,
+/// `owner:`, `group:`, `perm:` (as `--perm`), `mime:` and `kind:` (the type
+/// the file's first bytes show) and `content:`. A term without
+/// a field is matched like a plain query. `NOT` binds tightest, then `AND`
+/// (which can be left out), then `OR`; parentheses group. Quotes keep
+/// spaces, parentheses and keywords in a pattern.
+///
+/// However it's written, cheap tests on the path run before ones that need
+/// the file's metadata, and those before ones that read it.
+#[derive(Clone, Debug)]
+pub struct Expression {
+    source: String,
+    root: Node,
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    All(Vec<Node>),
+    Any(Vec<Node>),
+    Not(Box<Node>),
+    Test(Test),
+}
+
+#[derive(Clone, Debug)]
+enum Test {
+    // No field: matched the way the search matches a plain query
+    Query(String),
+    Part(MatchTarget, String),
+    Extensions(Vec<String>),
+    Kinds(Vec<FileKind>),
+    Size(Comparison, u64),
+    // How long ago the file was modified
+    Age(Comparison, Duration),
+    Owner(u32),
+    Group(u32),
+    Permissions(PermissionFilter),
+    // A MIME type pattern, matched against what the file's first bytes show
+    Mime(String),
+    Content(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn holds<T: PartialOrd>(self, value: T, limit: T) -> bool {
+        match self {
+            Comparison::Less => value < limit,
+            Comparison::LessOrEqual => value <= limit,
+            Comparison::Equal => value == limit,
+            Comparison::GreaterOrEqual => value >= limit,
+            Comparison::Greater => value > limit,
+        }
+    }
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, position: 0 };
+        
+        let root = parser.parse_or()?;
+        if let Some(token) = parser.tokens.get(parser.position) {
+            return Err(match token {
+                Token::Close => "unmatched ')'".to_string(),
+                _ => format!("unexpected {}", token),
+            });
+        }
+        
+        Ok(Self { source: source.to_string(), root })
+    }
+    
+    /// The expression as it was written.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+    
+    /// Turns the expression into tests ready to run, using the search's
+    /// settings for case, wildcards and the like.
+    pub(crate) fn compile(&self, config: &SearchConfig) -> Predicate {
+        compile(&self.root, config)
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+// ==============================================
+// PARSING
+// ==============================================
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    // `literal` if it was quoted from the start, so it's never a keyword or field
+    Term { text: String, literal: bool },
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Open => f.write_str("'('"),
+            Token::Close => f.write_str("')'"),
+            Token::And => f.write_str("AND"),
+            Token::Or => f.write_str("OR"),
+            Token::Not => f.write_str("NOT"),
+            Token::Term { text, .. } => write!(f, "'{}'", text),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == '(' || c == ')' {
+            chars.next();
+            tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            continue;
+        }
+        
+        let literal = c == '"';
+        let mut text = String::new();
+        let mut quoted = false;
+        while let Some(&c) = chars.peek() {
+            if c == '"' {
+                quoted = !quoted;
+            } else if !quoted && (c.is_whitespace() || c == '(' || c == ')') {
+                break;
+            } else {
+                text.push(c);
+            }
+            chars.next();
+        }
+        if quoted {
+            return Err("missing closing quote".to_string());
+        }
+        
+        tokens.push(match text.as_str() {
+            "AND" if !literal => Token::And,
+            "OR" if !literal => Token::Or,
+            "NOT" if !literal => Token::Not,
+            _ => Token::Term { text, literal },
+        });
+    }
+    
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+    
+    fn parse_or(&mut self) -> Result<Node, String> {
+        let mut nodes = vec![self.parse_and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            nodes.push(self.parse_and()?);
+        }
+        Ok(if nodes.len() == 1 { nodes.remove(0) } else { Node::Any(nodes) })
+    }
+    
+    fn parse_and(&mut self) -> Result<Node, String> {
+        let mut nodes = vec![self.parse_unary()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.position += 1,
+                // Terms next to each other are ANDed, as in find
+                Some(Token::Open | Token::Not | Token::Term { .. }) => {}
+                _ => break,
+            }
+            nodes.push(self.parse_unary()?);
+        }
+        Ok(if nodes.len() == 1 { nodes.remove(0) } else { Node::All(nodes) })
+    }
+    
+    fn parse_unary(&mut self) -> Result<Node, String> {
+        let token = match self.tokens.get(self.position) {
+            Some(token) => token,
+            None if self.position == 0 => return Err("the expression is empty".to_string()),
+            None => return Err(format!("expected a term after {}", self.tokens[self.position - 1])),
+        };
+        self.position += 1;
+        
+        match token {
+            Token::Not => Ok(Node::Not(Box::new(self.parse_unary()?))),
+            Token::Open => {
+                let node = self.parse_or()?;
+                if self.peek() != Some(&Token::Close) {
+                    return Err("missing ')'".to_string());
+                }
+                self.position += 1;
+                Ok(node)
+            }
+            Token::Term { text, literal } => Ok(Node::Test(parse_test(text, *literal)?)),
+            Token::Close | Token::And | Token::Or => Err(format!("expected a term, found {}", token)),
+        }
+    }
+}
+
+fn parse_test(text: &str, literal: bool) -> Result<Test, String> {
+    if literal {
+        return Ok(Test::Query(text.to_string()));
+    }
+    
+    for field in ["size", "mtime"] {
+        let rest = match text.strip_prefix(field) {
+            Some(rest) if rest.starts_with(['<', '>', '=']) => rest,
+            _ => continue,
+        };
+        let (comparison, value) = parse_comparison(rest);
+        return match field {
+            "size" => Ok(Test::Size(comparison, parse_size(value)?)),
+            _ if comparison == Comparison::Equal => Err("mtime can only be compared with < or >".to_string()),
+            _ => Ok(Test::Age(comparison, parse_age(value)?)),
+        };
+    }
+    
+    // Anything before a colon that isn't a field is part of the pattern
+    let (field, value) = match text.split_once(':') {
+        Some(split) => split,
+        None => return Ok(Test::Query(text.to_string())),
+    };
+    Ok(match field {
+        "name" => Test::Part(MatchTarget::Name, value.to_string()),
+        "path" => Test::Part(MatchTarget::Path, value.to_string()),
+        "relpath" => Test::Part(MatchTarget::RelativePath, value.to_string()),
+        "stem" => Test::Part(MatchTarget::Stem, value.to_string()),
+        "ext" => Test::Extensions(value.split(',')
+            .filter(|extension| !extension.is_empty())
+            .map(|extension| extension.strip_prefix('.').unwrap_or(extension).to_string())
+            .collect()),
+        "type" => Test::Kinds(parse_kinds(value)?),
+        "owner" => Test::Owner(parse_owner(value)?),
+        "group" => Test::Group(parse_group(value)?),
+        "perm" => Test::Permissions(PermissionFilter::parse(value)?),
+        "mime" if value.is_empty() => return Err("mime: needs a type like image/*".to_string()),
+        "mime" => Test::Mime(value.to_string()),
+        "kind" => Test::Mime(mime_for_kind(value).ok_or_else(|| format!("unknown kind '{}'", value))?.to_string()),
+        "content" if value.is_empty() => return Err("content: needs some text to look for".to_string()),
+        "content" => Test::Content(value.to_string()),
+        _ => Test::Query(text.to_string()),
+    })
+}
+
+fn parse_kinds(letters: &str) -> Result<Vec<FileKind>, String> {
+    letters.split(',')
+        .filter(|letter| !letter.is_empty())
+        .map(|letter| {
+            let mut chars = letter.chars();
+            match (chars.next().and_then(FileKind::from_letter), chars.next()) {
+                (Some(kind), None) => Ok(kind),
+                _ => Err(format!("unknown type '{}' (use f, d, l, p, s, b or c)", letter)),
+            }
+        })
+        .collect()
+}
+
+fn parse_comparison(text: &str) -> (Comparison, &str) {
+    for (operator, comparison) in [
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+        ("=", Comparison::Equal),
+    ] {
+        if let Some(value) = text.strip_prefix(operator) {
+            return (comparison, value);
+        }
+    }
+    (Comparison::Equal, text)
+}
+
+/// Sizes like `512`, `10K`, `1.5M` or `2GB`, in multiples of 1024.
+fn parse_size(text: &str) -> Result<u64, String> {
+    let split = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    
+    let value: f64 = number.parse().map_err(|_| format!("invalid size '{}'", text))?;
+    let multiplier: u64 = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1 << 10,
+        "M" | "MB" => 1 << 20,
+        "G" | "GB" => 1 << 30,
+        "T" | "TB" => 1 << 40,
+        _ => return Err(format!("invalid size unit in '{}' (use B, K, M, G or T)", text)),
+    };
+    Ok((value * multiplier as f64) as u64)
+}
+
+/// Ages like `30m`, `12h`, `7d` or `2w`.
+fn parse_age(text: &str) -> Result<Duration, String> {
+    let split = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    
+    let value: f64 = number.parse().map_err(|_| format!("invalid age '{}'", text))?;
+    let seconds = match unit {
+        "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        "d" => value * 86400.0,
+        "w" => value * 7.0 * 86400.0,
+        _ => return Err(format!("invalid age unit in '{}' (use s, m, h, d or w)", text)),
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("invalid age '{}'", text))
+}
+
+#[cfg(unix)]
+fn parse_owner(name: &str) -> Result<u32, String> {
+    crate::accounts::user_id(name).ok_or_else(|| format!("unknown user '{}'", name))
+}
+
+#[cfg(not(unix))]
+fn parse_owner(_name: &str) -> Result<u32, String> {
+    Err("owner: is only supported on Unix".to_string())
+}
+
+#[cfg(unix)]
+fn parse_group(name: &str) -> Result<u32, String> {
+    crate::accounts::group_id(name).ok_or_else(|| format!("unknown group '{}'", name))
+}
+
+#[cfg(not(unix))]
+fn parse_group(_name: &str) -> Result<u32, String> {
+    Err("group: is only supported on Unix".to_string())
+}
+
+// ==============================================
+// EVALUATION
+// ==============================================
+
+/// A compiled expression.
+pub(crate) enum Predicate {
+    All(Vec<Predicate>),
+    Any(Vec<Predicate>),
+    Not(Box<Predicate>),
+    Part(MatchTarget, Pattern),
+    Extensions(Vec<Pattern>),
+    Kinds(Vec<FileKind>),
+    Size(Comparison, u64),
+    Age(Comparison, Duration),
+    Owner(u32),
+    Group(u32),
+    Permissions(PermissionFilter),
+    Mime(Pattern),
+    Content { text: Vec<u8>, ignore_case: bool },
+}
+
+/// A path being tested; its metadata is only read if a test needs it.
+pub(crate) struct Entry<'a> {
+    path: &'a Path,
+    root: Option<&'a Path>,
+    // From the directory listing, so free to check
+    kind: FileKind,
+    metadata: OnceCell<Option<Metadata>>,
+    // A symlink's own, not what it points to
+    #[cfg(unix)]
+    link_metadata: OnceCell<Option<Metadata>>,
+    mime: OnceCell<Option<&'static str>>,
+}
+
+impl<'a> Entry<'a> {
+    pub fn new(path: &'a Path, root: Option<&'a Path>, kind: FileKind) -> Self {
+        Self {
+            path,
+            root,
+            kind,
+            metadata: OnceCell::new(),
+            #[cfg(unix)]
+            link_metadata: OnceCell::new(),
+            mime: OnceCell::new(),
+        }
+    }
+    
+    // What a symlink points to, or the link itself if it's broken
+    fn metadata(&self) -> Option<&Metadata> {
+        self.metadata
+            .get_or_init(|| fs::metadata(self.path).or_else(|_| fs::symlink_metadata(self.path)).ok())
+            .as_ref()
+    }
+    
+    // The entry itself, for its owner and permissions: as with find, a
+    // symlink's are the link's own rather than its target's
+    #[cfg(unix)]
+    fn own_metadata(&self) -> Option<&Metadata> {
+        if self.kind != FileKind::Symlink {
+            return self.metadata();
+        }
+        self.link_metadata.get_or_init(|| fs::symlink_metadata(self.path).ok()).as_ref()
+    }
+    
+    /// The MIME type its first bytes show; only files have one.
+    pub fn mime(&self) -> Option<&'static str> {
+        *self.mime.get_or_init(|| {
+            self.metadata().is_some_and(Metadata::is_file).then(|| detect_mime(self.path)).flatten()
+        })
+    }
+    
+    /// A zero-byte file, or a directory with nothing in it.
+    pub fn is_empty(&self) -> bool {
+        match self.kind {
+            FileKind::File => self.metadata().is_some_and(|metadata| metadata.len() == 0),
+            FileKind::Directory => fs::read_dir(self.path).is_ok_and(|mut entries| entries.next().is_none()),
+            _ => false,
+        }
+    }
+    
+    /// A file that can be run; a symlink counts if what it points to can.
+    pub fn is_executable(&self) -> bool {
+        let metadata = match self.metadata() {
+            Some(metadata) if metadata.is_file() => metadata,
+            _ => return false,
+        };
+        
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            metadata.permissions().mode() & 0o111 != 0
+        }
+        
+        #[cfg(not(unix))]
+        {
+            let _ = metadata;
+            self.path.extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| {
+                    ["exe", "com", "bat", "cmd"].iter().any(|program| extension.eq_ignore_ascii_case(program))
+                })
+        }
+    }
+    
+    /// The ID of the user that owns it, or owns the link for a symlink;
+    /// `None` off Unix.
+    #[cfg(unix)]
+    pub fn uid(&self) -> Option<u32> {
+        self.own_metadata().map(MetadataExt::uid)
+    }
+    
+    #[cfg(not(unix))]
+    pub fn uid(&self) -> Option<u32> {
+        None
+    }
+    
+    /// The ID of its group, or the link's for a symlink; `None` off Unix.
+    #[cfg(unix)]
+    pub fn gid(&self) -> Option<u32> {
+        self.own_metadata().map(MetadataExt::gid)
+    }
+    
+    #[cfg(not(unix))]
+    pub fn gid(&self) -> Option<u32> {
+        None
+    }
+    
+    /// Its permission bits, with setuid, setgid and sticky, or the link's
+    /// for a symlink; `None` off Unix.
+    #[cfg(unix)]
+    pub fn mode(&self) -> Option<u32> {
+        self.own_metadata().map(|metadata| metadata.mode() & 0o7777)
+    }
+    
+    #[cfg(not(unix))]
+    pub fn mode(&self) -> Option<u32> {
+        None
+    }
+    
+    /// Owned by a user ID that no account has.
+    #[cfg(unix)]
+    pub fn has_unknown_user(&self) -> bool {
+        self.uid().is_some_and(|uid| !crate::accounts::user_exists(uid))
+    }
+    
+    #[cfg(not(unix))]
+    pub fn has_unknown_user(&self) -> bool {
+        false
+    }
+    
+    /// In a group ID that no group has.
+    #[cfg(unix)]
+    pub fn has_unknown_group(&self) -> bool {
+        self.gid().is_some_and(|gid| !crate::accounts::group_exists(gid))
+    }
+    
+    #[cfg(not(unix))]
+    pub fn has_unknown_group(&self) -> bool {
+        false
+    }
+}
+
+fn compile(node: &Node, config: &SearchConfig) -> Predicate {
+    let pattern = |query: &str| {
+        Pattern::new(query, config.case_sensitive_for(query), config.use_wildcards)
+            .normalized(config.normalization)
+            .ignore_accents(config.ignore_accents)
+    };
+    
+    match node {
+        Node::All(nodes) => Predicate::All(compile_ordered(nodes, config)),
+        Node::Any(nodes) => Predicate::Any(compile_ordered(nodes, config)),
+        Node::Not(node) => Predicate::Not(Box::new(compile(node, config))),
+        Node::Test(test) => match test {
+            Test::Query(query) => Predicate::Part(config.match_target, pattern(query)),
+            Test::Part(target, query) => Predicate::Part(*target, pattern(query)),
+            // The same as --ext
+            Test::Extensions(extensions) => Predicate::Extensions(extensions.iter()
+                .map(|extension| {
+                    Pattern::new(&format!("?*.{}", extension), false, true)
+                        .normalized(config.normalization)
+                        .ignore_accents(config.ignore_accents)
+                })
+                .collect()),
+            Test::Kinds(kinds) => Predicate::Kinds(kinds.clone()),
+            Test::Size(comparison, size) => Predicate::Size(*comparison, *size),
+            Test::Age(comparison, age) => Predicate::Age(*comparison, *age),
+            Test::Owner(uid) => Predicate::Owner(*uid),
+            Test::Group(gid) => Predicate::Group(*gid),
+            Test::Permissions(filter) => Predicate::Permissions(*filter),
+            Test::Mime(mime) => Predicate::Mime(Pattern::new(mime, false, true)),
+            Test::Content(text) => Predicate::Content {
+                text: text.as_bytes().to_vec(),
+                ignore_case: !config.case_sensitive_for(text),
+            },
+        },
+    }
+}
+
+// AND and OR don't care about order, so the cheapest tests go first and
+// the expensive ones are often never reached
+fn compile_ordered(nodes: &[Node], config: &SearchConfig) -> Vec<Predicate> {
+    let mut predicates: Vec<Predicate> = nodes.iter().map(|node| compile(node, config)).collect();
+    predicates.sort_by_key(Predicate::cost);
+    predicates
+}
+
+impl Predicate {
+    // 0 for the path alone, 1 for metadata, 2 for reading the file
+    fn cost(&self) -> u8 {
+        match self {
+            Predicate::All(predicates) | Predicate::Any(predicates) => {
+                predicates.iter().map(Predicate::cost).max().unwrap_or(0)
+            }
+            Predicate::Not(predicate) => predicate.cost(),
+            Predicate::Part(..) | Predicate::Extensions(_) | Predicate::Kinds(_) => 0,
+            Predicate::Size(..) | Predicate::Age(..) => 1,
+            Predicate::Owner(_) | Predicate::Group(_) | Predicate::Permissions(_) => 1,
+            Predicate::Mime(_) | Predicate::Content { .. } => 2,
+        }
+    }
+    
+    pub(crate) fn evaluate(&self, entry: &Entry) -> bool {
+        match self {
+            Predicate::All(predicates) => predicates.iter().all(|predicate| predicate.evaluate(entry)),
+            Predicate::Any(predicates) => predicates.iter().any(|predicate| predicate.evaluate(entry)),
+            Predicate::Not(predicate) => !predicate.evaluate(entry),
+            Predicate::Part(target, pattern) => target.select(entry.path, entry.root)
+                .is_some_and(|text| pattern.matches_os(text)),
+            Predicate::Extensions(patterns) => entry.path.file_name()
+                .is_some_and(|name| patterns.iter().any(|pattern| pattern.matches_os(name))),
+            Predicate::Kinds(kinds) => kinds.contains(&entry.kind),
+            Predicate::Size(comparison, size) => entry.metadata()
+                .is_some_and(|metadata| comparison.holds(metadata.len(), *size)),
+            Predicate::Age(comparison, age) => entry.metadata()
+                .and_then(|metadata| metadata.modified().ok())
+                .is_some_and(|modified| {
+                    // Files from the future are brand new
+                    let elapsed = SystemTime::now().duration_since(modified).unwrap_or_default();
+                    comparison.holds(elapsed, *age)
+                }),
+            Predicate::Owner(uid) => entry.uid() == Some(*uid),
+            Predicate::Group(gid) => entry.gid() == Some(*gid),
+            Predicate::Permissions(filter) => entry.mode().is_some_and(|mode| filter.matches(mode)),
+            Predicate::Mime(pattern) => entry.mime().is_some_and(|mime| pattern.matches(mime)),
+            Predicate::Content { text, ignore_case } => entry.metadata().is_some_and(Metadata::is_file)
+                && file_contains(entry.path, text, *ignore_case).unwrap_or(false),
+        }
+    }
+}
+
+/// Whether the file holds `text`, read a chunk at a time. With
+/// `ignore_case` only ASCII letters are compared case-insensitively.
+fn file_contains(path: &Path, text: &[u8], ignore_case: bool) -> io::Result<bool> {
+    let mut file = File::open(path)?;
+    let mut buffer = vec![0; CONTENT_CHUNK + text.len()];
+    let mut kept = 0;
+    
+    loop {
+        let read = match file.read(&mut buffer[kept..]) {
+            Ok(0) => return Ok(false),
+            Ok(read) => read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+        let filled = kept + read;
+        
+        let found = buffer[..filled].windows(text.len()).any(|window| {
+            if ignore_case { window.eq_ignore_ascii_case(text) } else { window == text }
+        });
+        if found {
+            return Ok(true);
+        }
+        
+        // Keep the end in case the text straddles two reads
+        kept = (text.len() - 1).min(filled);
+        buffer.copy_within(filled - kept..filled, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn term(text: &str) -> Token {
+        Token::Term { text: text.to_string(), literal: false }
+    }
+    
+    fn compiled(source: &str) -> Predicate {
+        Expression::parse(source).unwrap().compile(&SearchConfig::builder("").build())
+    }
+    
+    // Whether a file at `path` that doesn't exist passes, so only tests on
+    // the path can succeed
+    fn accepts(source: &str, path: &str) -> bool {
+        compiled(source).evaluate(&Entry::new(Path::new(path), None, FileKind::File))
+    }
+    
+    #[test]
+    fn tokens_split_on_spaces_and_parentheses() {
+        assert_eq!(tokenize("(name:*.rs OR ext:toml)AND NOT x").unwrap(), [
+            Token::Open,
+            term("name:*.rs"),
+            Token::Or,
+            term("ext:toml"),
+            Token::Close,
+            Token::And,
+            Token::Not,
+            term("x"),
+        ]);
+        assert!(tokenize("  ").unwrap().is_empty());
+    }
+    
+    #[test]
+    fn quotes_keep_spaces_and_keywords() {
+        assert_eq!(tokenize(r#"name:"my notes (old).txt""#).unwrap(), [term("name:my notes (old).txt")]);
+        assert_eq!(tokenize(r#""AND" "size>1""#).unwrap(), [
+            Token::Term { text: "AND".to_string(), literal: true },
+            Token::Term { text: "size>1".to_string(), literal: true },
+        ]);
+        // Only the uppercase words are keywords
+        assert_eq!(tokenize("and or not").unwrap(), [term("and"), term("or"), term("not")]);
+        assert_eq!(tokenize(r#"name:"open"#).unwrap_err(), "missing closing quote");
+    }
+    
+    #[test]
+    fn not_binds_tighter_than_and_and_and_than_or() {
+        let expression = Expression::parse("a OR NOT b c").unwrap();
+        match expression.root {
+            Node::Any(ref nodes) => {
+                assert!(matches!(nodes[0], Node::Test(Test::Query(ref q)) if q == "a"));
+                match nodes[1] {
+                    Node::All(ref all) => {
+                        assert!(matches!(all[0], Node::Not(_)));
+                        assert!(matches!(all[1], Node::Test(Test::Query(ref q)) if q == "c"));
+                    }
+                    ref other => panic!("expected AND, got {:?}", other),
+                }
+            }
+            ref other => panic!("expected OR, got {:?}", other),
+        }
+        
+        let grouped = Expression::parse("(a OR b) c").unwrap();
+        assert!(matches!(grouped.root, Node::All(ref nodes) if matches!(nodes[0], Node::Any(_))));
+    }
+    
+    #[test]
+    fn fields_become_tests() {
+        let test = |source: &str| match Expression::parse(source).unwrap().root {
+            Node::Test(test) => test,
+            other => panic!("expected a single test, got {:?}", other),
+        };
+        
+        assert!(matches!(test("name:*.rs"), Test::Part(MatchTarget::Name, ref p) if p == "*.rs"));
+        assert!(matches!(test("relpath:src/*"), Test::Part(MatchTarget::RelativePath, _)));
+        assert!(matches!(test("ext:.rs,toml,"), Test::Extensions(ref e) if e == &["rs", "toml"]));
+        assert!(matches!(test("type:f,d"), Test::Kinds(ref k) if k == &[FileKind::File, FileKind::Directory]));
+        assert!(matches!(test("size>=1.5K"), Test::Size(Comparison::GreaterOrEqual, 1536)));
+        assert!(matches!(test("size=0"), Test::Size(Comparison::Equal, 0)));
+        assert!(matches!(test("mtime<2h"), Test::Age(Comparison::Less, age) if age == Duration::from_secs(7200)));
+        assert!(matches!(test("kind:png"), Test::Mime(ref m) if m == "image/png"));
+        assert!(matches!(test("content:TODO"), Test::Content(ref c) if c == "TODO"));
+        // Not a field, so the colon is part of the pattern
+        assert!(matches!(test("C:*"), Test::Query(ref q) if q == "C:*"));
+        assert!(matches!(test(r#""name:x""#), Test::Query(ref q) if q == "name:x"));
+    }
+    
+    #[test]
+    fn mistakes_are_reported() {
+        let error = |source: &str| Expression::parse(source).expect_err(source);
+        assert_eq!(error(""), "the expression is empty");
+        assert_eq!(error("a AND"), "expected a term after AND");
+        assert_eq!(error("a OR OR b"), "expected a term, found OR");
+        assert_eq!(error("(a"), "missing ')'");
+        assert_eq!(error("a)"), "unmatched ')'");
+        assert_eq!(error("mtime=1d"), "mtime can only be compared with < or >");
+        assert!(error("size>10X").contains("invalid size unit"));
+        assert!(error("mtime>1y").contains("invalid age unit"));
+        assert!(error("type:q").contains("unknown type 'q'"));
+        assert!(error("kind:nonsense").contains("unknown kind"));
+        assert!(error("content:").contains("needs some text"));
+    }
+    
+    #[test]
+    fn cheap_tests_run_first() {
+        let costs = |predicate: &Predicate| match predicate {
+            Predicate::All(predicates) | Predicate::Any(predicates) => {
+                predicates.iter().map(Predicate::cost).collect::<Vec<_>>()
+            }
+            _ => panic!("expected AND or OR"),
+        };
+        
+        assert_eq!(costs(&compiled("content:x size>1 name:*.rs")), [0, 1, 2]);
+        assert_eq!(costs(&compiled("mime:text/* OR perm:644 OR type:f")), [0, 1, 2]);
+        // A group costs as much as its dearest test
+        assert_eq!(costs(&compiled("(name:a OR content:x) size>1 ext:rs")), [0, 1, 2]);
+        assert_eq!(costs(&compiled("NOT content:x name:a")), [0, 2]);
+    }
+    
+    #[test]
+    fn predicates_are_compiled_cheapest_first() {
+        let children = |predicate: Predicate| match predicate {
+            Predicate::All(predicates) | Predicate::Any(predicates) => predicates,
+            _ => panic!("expected AND or OR"),
+        };
+        
+        let predicates = children(compiled("content:x mime:text/* perm:644 size>1 type:f name:*.rs"));
+        assert!(matches!(
+            predicates.as_slice(),
+            [
+                Predicate::Kinds(_),
+                Predicate::Part(MatchTarget::Name, _),
+                Predicate::Permissions(_),
+                Predicate::Size(..),
+                Predicate::Content { .. },
+                Predicate::Mime(_),
+            ]
+        ));
+        
+        // Within each group too, and the sort keeps the written order among equals
+        let predicates = children(compiled("(content:x OR ext:rs) NOT size>1 stem:a"));
+        assert!(matches!(predicates.as_slice(), [Predicate::Part(MatchTarget::Stem, _), Predicate::Not(_), Predicate::Any(_)]));
+        match &predicates[2] {
+            Predicate::Any(inner) => assert!(matches!(inner.as_slice(), [Predicate::Extensions(_), Predicate::Content { .. }])),
+            _ => unreachable!(),
+        }
+    }
+    
+    #[test]
+    fn expensive_tests_are_skipped_when_the_path_decides() {
+        // The entry only reads its metadata and first bytes when a test
+        // asks, so untouched caches show the test was never reached
+        let entry = Entry::new(Path::new("Cargo.toml"), None, FileKind::File);
+        assert!(!compiled("mime:text/* size>0 name:*.rs").evaluate(&entry));
+        assert!(entry.metadata.get().is_none());
+        assert!(entry.mime.get().is_none());
+        
+        assert!(compiled("mime:text/* OR size>0 OR name:*.toml").evaluate(&entry));
+        assert!(entry.metadata.get().is_none());
+        
+        // Once the path doesn't decide, they are reached
+        assert!(compiled("size>0 name:*.toml").evaluate(&entry));
+        assert!(entry.metadata.get().is_some());
+        assert!(entry.mime.get().is_none());
+    }
+    
+    #[test]
+    fn path_tests_are_evaluated() {
+        assert!(accepts("name:*.rs AND NOT name:test_*", "src/main.rs"));
+        assert!(!accepts("name:*.rs AND NOT name:test_*", "src/test_main.rs"));
+        assert!(accepts("ext:rs,toml", "Cargo.toml"));
+        assert!(!accepts("ext:rs", ".rs"));
+        assert!(accepts("type:f,l", "a"));
+        assert!(!accepts("type:d", "a"));
+        assert!(accepts("stem:main", "src/main.rs"));
+    }
+}
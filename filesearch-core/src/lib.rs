@@ -0,0 +1,69 @@
+//! The FileSearch engine: a multi-threaded, cross-platform search for files
+//! and directories by name.
+//!
+//! This is the engine behind the `filesearch` command and the GUI version.
+//! It never prints or reads from stdin; matches, errors and progress are
+//! sent as [`SearchEvent`]s to every [`subscribe`](SearchEngine::subscribe)d
+//! channel, or matches alone can be consumed as an iterator.
+//!
+//! ```no_run
+//! use filesearch_core::{SearchConfig, SearchEngine, SearchMode};
+//!
+//! let config = SearchConfig::builder("*.rs")
+//!     .mode(SearchMode::File)
+//!     .start_dir(".")
+//!     .build();
+//!
+//! for found in SearchEngine::new(config).into_results() {
+//!     println!("{}", found.path.display());
+//! }
+//! ```
+//!
+//! A front-end that wants progress as well subscribes before starting:
+//!
+//! ```no_run
+//! use filesearch_core::{SearchConfig, SearchEngine, SearchEvent};
+//!
+//! let mut engine = SearchEngine::new(SearchConfig::builder("notes").start_dir(".").build());
+//! let events = engine.subscribe();
+//! let search = engine.spawn();
+//!
+//! for event in events {
+//!     match event {
+//!         SearchEvent::Progress { dirs, rate, .. } => println!("{} dirs, {:.0} files/sec", dirs, rate),
+//!         SearchEvent::Finished { stats, .. } => println!("{} matches", stats.matches_found),
+//!         _ => {}
+//!     }
+//! }
+//! let engine = search.join();
+//! println!("{:?}", engine.stop_reason());
+//! ```
+
+#[cfg(unix)]
+mod accounts;
+mod checkpoint;
+mod config;
+mod engine;
+mod escape;
+mod expression;
+mod mime;
+mod pattern;
+mod permissions;
+mod platform;
+mod unicode;
+mod unicode_tables;
+
+pub use checkpoint::Checkpoint;
+pub use config::{FileKind, MatchTarget, SearchConfig, SearchConfigBuilder, SearchMode};
+pub use engine::{
+    Match, Results, SearchEngine, SearchError, SearchErrorKind, SearchEvent, SearchHandle, SearchStats, StopReason,
+};
+pub use escape::{decode_path, display_path, encode_path, escape_text, unescape_text};
+pub use expression::Expression;
+pub use mime::{detect_mime, known_kinds, mime_for_kind, mime_of};
+#[cfg(unix)]
+pub use accounts::{group_id, user_id};
+pub use pattern::{FuzzyPattern, Pattern};
+pub use permissions::{PermissionFilter, PermissionMatch};
+pub use platform::{arch_name, os_name, system_description};
+pub use unicode::Normalization;
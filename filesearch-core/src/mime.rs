@@ -0,0 +1,334 @@
+//! File types told apart by their first bytes, for when extensions lie.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+// Enough to reach the tar header's magic at offset 257, and the PE
+// header and second MP3 frame of most files
+const HEADER_LEN: usize = 1024;
+
+struct Signature {
+    // Every (offset, bytes) pair must be present
+    parts: &'static [(usize, &'static [u8])],
+    // A closer look at the rest of the header, for signatures so short
+    // that other files often start the same way
+    check: Option<fn(&[u8]) -> bool>,
+    mime: &'static str,
+    // The short name --kind accepts
+    kind: &'static str,
+}
+
+const fn sig(parts: &'static [(usize, &'static [u8])], mime: &'static str, kind: &'static str) -> Signature {
+    Signature { parts, check: None, mime, kind }
+}
+
+const fn checked_sig(
+    parts: &'static [(usize, &'static [u8])],
+    check: fn(&[u8]) -> bool,
+    mime: &'static str,
+    kind: &'static str,
+) -> Signature {
+    Signature { parts, check: Some(check), mime, kind }
+}
+
+// Checked in order, so longer signatures come before shorter ones they share a prefix with
+const SIGNATURES: &[Signature] = &[
+    // Images
+    sig(&[(0, b"\x89PNG\r\n\x1a\n")], "image/png", "png"),
+    sig(&[(0, b"\xff\xd8\xff")], "image/jpeg", "jpeg"),
+    sig(&[(0, b"GIF87a")], "image/gif", "gif"),
+    sig(&[(0, b"GIF89a")], "image/gif", "gif"),
+    sig(&[(0, b"RIFF"), (8, b"WEBP")], "image/webp", "webp"),
+    sig(&[(0, b"II*\0")], "image/tiff", "tiff"),
+    sig(&[(0, b"MM\0*")], "image/tiff", "tiff"),
+    sig(&[(0, b"8BPS")], "image/vnd.adobe.photoshop", "psd"),
+    checked_sig(&[(0, b"\0\0\x01\0")], is_icon, "image/vnd.microsoft.icon", "ico"),
+    sig(&[(4, b"ftypheic")], "image/heic", "heic"),
+    sig(&[(4, b"ftypmif1")], "image/heic", "heic"),
+    checked_sig(&[(0, b"BM")], is_bitmap, "image/bmp", "bmp"),
+    // Documents
+    sig(&[(0, b"%PDF-")], "application/pdf", "pdf"),
+    sig(&[(0, b"%!PS")], "application/postscript", "ps"),
+    sig(&[(0, b"{\\rtf")], "application/rtf", "rtf"),
+    sig(&[(0, b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1")], "application/x-ole-storage", "ole"),
+    sig(&[(0, b"<?xml")], "application/xml", "xml"),
+    // Archives and compression
+    sig(&[(0, b"PK\x03\x04")], "application/zip", "zip"),
+    sig(&[(0, b"PK\x05\x06")], "application/zip", "zip"),
+    sig(&[(0, b"\x1f\x8b")], "application/gzip", "gzip"),
+    sig(&[(0, b"BZh")], "application/x-bzip2", "bzip2"),
+    sig(&[(0, b"\xfd7zXZ\0")], "application/x-xz", "xz"),
+    sig(&[(0, b"\x28\xb5\x2f\xfd")], "application/zstd", "zstd"),
+    sig(&[(0, b"7z\xbc\xaf\x27\x1c")], "application/x-7z-compressed", "7z"),
+    sig(&[(0, b"Rar!\x1a\x07")], "application/vnd.rar", "rar"),
+    sig(&[(257, b"ustar")], "application/x-tar", "tar"),
+    sig(&[(0, b"!<arch>\n")], "application/x-archive", "ar"),
+    // Programs
+    sig(&[(0, b"\x7fELF")], "application/x-executable", "elf"),
+    sig(&[(0, b"\xfe\xed\xfa\xce")], "application/x-mach-binary", "macho"),
+    sig(&[(0, b"\xfe\xed\xfa\xcf")], "application/x-mach-binary", "macho"),
+    sig(&[(0, b"\xce\xfa\xed\xfe")], "application/x-mach-binary", "macho"),
+    sig(&[(0, b"\xcf\xfa\xed\xfe")], "application/x-mach-binary", "macho"),
+    checked_sig(&[(0, b"MZ")], is_portable_executable, "application/vnd.microsoft.portable-executable", "exe"),
+    sig(&[(0, b"\0asm")], "application/wasm", "wasm"),
+    sig(&[(0, b"\xca\xfe\xba\xbe")], "application/java-vm", "class"),
+    // Data
+    sig(&[(0, b"SQLite format 3\0")], "application/vnd.sqlite3", "sqlite"),
+    // Audio and video
+    sig(&[(0, b"ID3")], "audio/mpeg", "mp3"),
+    checked_sig(&[(0, b"\xff\xfb")], is_mp3_frame, "audio/mpeg", "mp3"),
+    checked_sig(&[(0, b"\xff\xf3")], is_mp3_frame, "audio/mpeg", "mp3"),
+    sig(&[(0, b"fLaC")], "audio/flac", "flac"),
+    sig(&[(0, b"OggS")], "audio/ogg", "ogg"),
+    sig(&[(0, b"RIFF"), (8, b"WAVE")], "audio/wav", "wav"),
+    sig(&[(0, b"RIFF"), (8, b"AVI ")], "video/x-msvideo", "avi"),
+    sig(&[(4, b"ftypM4A ")], "audio/mp4", "m4a"),
+    sig(&[(4, b"ftypqt  ")], "video/quicktime", "mov"),
+    sig(&[(4, b"ftyp")], "video/mp4", "mp4"),
+    sig(&[(0, b"\x1a\x45\xdf\xa3")], "video/x-matroska", "mkv"),
+    // Fonts
+    sig(&[(0, b"wOFF")], "font/woff", "woff"),
+    sig(&[(0, b"wOF2")], "font/woff2", "woff2"),
+    sig(&[(0, b"OTTO")], "font/otf", "otf"),
+    sig(&[(0, b"\0\x01\0\0\0")], "font/ttf", "ttf"),
+];
+
+// Other names people reach for
+const KIND_ALIASES: &[(&str, &str)] = &[
+    ("jpg", "jpeg"),
+    ("tif", "tiff"),
+    ("gz", "gzip"),
+    ("bz2", "bzip2"),
+    ("zst", "zstd"),
+    ("sqlite3", "sqlite"),
+    ("webm", "mkv"),
+];
+
+/// The MIME type of the file at `path`, judged by its first bytes. `None`
+/// if it matches no known signature or can't be read.
+pub fn detect_mime(path: &Path) -> Option<&'static str> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    File::open(path).ok()?.take(HEADER_LEN as u64).read_to_end(&mut header).ok()?;
+    mime_of(&header)
+}
+
+/// The MIME type of data starting with `header`, if it has a known signature.
+pub fn mime_of(header: &[u8]) -> Option<&'static str> {
+    SIGNATURES.iter()
+        .find(|signature| {
+            signature.parts.iter().all(|&(offset, magic)| header.get(offset..offset + magic.len()) == Some(magic))
+                && signature.check.is_none_or(|check| check(header))
+        })
+        .map(|signature| signature.mime)
+}
+
+/// The MIME type for a short name like `pdf`, `png` or `jpg`.
+pub fn mime_for_kind(kind: &str) -> Option<&'static str> {
+    let kind = kind.to_ascii_lowercase();
+    let kind = KIND_ALIASES.iter()
+        .find(|&&(alias, _)| alias == kind)
+        .map_or(kind.as_str(), |&(_, name)| name);
+    SIGNATURES.iter().find(|signature| signature.kind == kind).map(|signature| signature.mime)
+}
+
+/// The short names [`mime_for_kind`] knows, without aliases.
+pub fn known_kinds() -> Vec<&'static str> {
+    let mut kinds: Vec<&str> = SIGNATURES.iter().map(|signature| signature.kind).collect();
+    kinds.sort_unstable();
+    kinds.dedup();
+    kinds
+}
+
+fn u16_at(header: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(header.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn u32_at(header: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(header.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+// The file header's reserved fields are zero, and the info header that
+// follows it has one of the sizes the format's versions use
+fn is_bitmap(header: &[u8]) -> bool {
+    match (u32_at(header, 6), u32_at(header, 10), u32_at(header, 14)) {
+        (Some(reserved), Some(data), Some(info_size)) => {
+            reserved == 0 && [12, 16, 40, 52, 56, 64, 108, 124].contains(&info_size) && data >= 14 + info_size
+        }
+        _ => false,
+    }
+}
+
+// The DOS stub says where the PE header is, and it has to be there
+fn is_portable_executable(header: &[u8]) -> bool {
+    u32_at(header, 0x3c)
+        .and_then(|offset| header.get(offset as usize..offset as usize + 4))
+        .is_some_and(|magic| magic == b"PE\0\0")
+}
+
+// At least one image, and the first entry is laid out as one: a zero
+// reserved byte, 0 or 1 colour planes and its data after the directory
+fn is_icon(header: &[u8]) -> bool {
+    let count = u16_at(header, 4).unwrap_or(0);
+    count > 0
+        && header.get(9) == Some(&0)
+        && u16_at(header, 10).is_some_and(|planes| planes <= 1)
+        && u32_at(header, 18).is_some_and(|data| data >= 6 + 16 * u32::from(count))
+}
+
+// The frame header's bitrate and sample rate are real ones, and the next
+// frame starts with a frame sync too, unless it's past the header. Data
+// that ends before the first frame does isn't taken for an MP3
+fn is_mp3_frame(header: &[u8]) -> bool {
+    // Layer III bitrates in kbit/s for MPEG-1, then MPEG-2, by index
+    const BITRATES: [[u32; 15]; 2] = [
+        [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320],
+        [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+    ];
+    const SAMPLE_RATES: [[u32; 3]; 2] = [[44100, 48000, 32000], [22050, 24000, 16000]];
+    
+    let (Some(&version), Some(&info)) = (header.get(1), header.get(2)) else {
+        return false;
+    };
+    let mpeg2 = usize::from(version == 0xf3);
+    let bitrate_index = usize::from(info >> 4);
+    let rate_index = usize::from((info >> 2) & 0b11);
+    // 0 is "free format", which can't be measured, and 15 and 3 are invalid
+    if bitrate_index == 0 || bitrate_index == 15 || rate_index == 3 {
+        return false;
+    }
+    
+    let bitrate = BITRATES[mpeg2][bitrate_index] * 1000;
+    let sample_rate = SAMPLE_RATES[mpeg2][rate_index];
+    let padding = u32::from((info >> 1) & 1);
+    let frame_len = (if mpeg2 == 1 { 72 } else { 144 } * bitrate / sample_rate + padding) as usize;
+    match header.get(frame_len..frame_len + 2) {
+        Some(next) => next[0] == 0xff && next[1] & 0xe0 == 0xe0,
+        None => header.len() >= HEADER_LEN.min(frame_len),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // `prefix` padded with zeros to `len`, with `parts` written over it
+    fn header(prefix: &[u8], len: usize, parts: &[(usize, &[u8])]) -> Vec<u8> {
+        let mut header = prefix.to_vec();
+        header.resize(len, 0);
+        for &(offset, bytes) in parts {
+            header[offset..offset + bytes.len()].copy_from_slice(bytes);
+        }
+        header
+    }
+    
+    fn bitmap() -> Vec<u8> {
+        header(b"BM", 64, &[(2, &64u32.to_le_bytes()), (10, &54u32.to_le_bytes()), (14, &40u32.to_le_bytes())])
+    }
+    
+    fn executable() -> Vec<u8> {
+        header(b"MZ\x90\0", 256, &[(0x3c, &0x80u32.to_le_bytes()), (0x80, b"PE\0\0")])
+    }
+    
+    // 128 kbit/s at 44.1 kHz, so each frame is 417 bytes
+    fn mp3() -> Vec<u8> {
+        header(b"\xff\xfb\x90\0", HEADER_LEN, &[(417, b"\xff\xfb\x90\0")])
+    }
+    
+    fn icon() -> Vec<u8> {
+        header(b"\0\0\x01\0\x01\0", 64, &[(6, b"\x10\x10\0\0\x01\0\x20\0"), (18, &22u32.to_le_bytes())])
+    }
+    
+    #[test]
+    fn signatures_are_recognised() {
+        assert_eq!(mime_of(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some("image/png"));
+        assert_eq!(mime_of(b"\xff\xd8\xff\xe0\0\x10JFIF"), Some("image/jpeg"));
+        assert_eq!(mime_of(b"%PDF-1.7\n"), Some("application/pdf"));
+        assert_eq!(mime_of(b"PK\x03\x04\x14\0"), Some("application/zip"));
+        assert_eq!(mime_of(b"\x7fELF\x02\x01\x01"), Some("application/x-executable"));
+        assert_eq!(mime_of(b"ID3\x04\0\0"), Some("audio/mpeg"));
+        assert_eq!(mime_of(&header(b"", 512, &[(257, b"ustar\0")])), Some("application/x-tar"));
+    }
+    
+    #[test]
+    fn shared_prefixes_are_told_apart() {
+        assert_eq!(mime_of(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(mime_of(b"RIFF\0\0\0\0WAVEfmt "), Some("audio/wav"));
+        assert_eq!(mime_of(b"RIFF\0\0\0\0AVI LIST"), Some("video/x-msvideo"));
+        assert_eq!(mime_of(b"RIFF\0\0\0\0????"), None);
+        assert_eq!(mime_of(b"\0\0\0\x18ftypheic"), Some("image/heic"));
+        assert_eq!(mime_of(b"\0\0\0\x18ftypM4A "), Some("audio/mp4"));
+        assert_eq!(mime_of(b"\0\0\0\x18ftypisom"), Some("video/mp4"));
+    }
+    
+    #[test]
+    fn short_signatures_need_a_real_header() {
+        assert_eq!(mime_of(&bitmap()), Some("image/bmp"));
+        assert_eq!(mime_of(&executable()), Some("application/vnd.microsoft.portable-executable"));
+        assert_eq!(mime_of(&mp3()), Some("audio/mpeg"));
+        assert_eq!(mime_of(&icon()), Some("image/vnd.microsoft.icon"));
+    }
+    
+    #[test]
+    fn text_that_starts_like_a_signature_is_not_detected() {
+        assert_eq!(mime_of(b"BMW 320i service history\n2019-04-02  oil change\n"), None);
+        assert_eq!(mime_of(b"MZ-80 emulator notes\nThe Sharp MZ-80K was released in 1979 and came with a built-in cassette deck.\n"), None);
+        assert_eq!(mime_of(b"BM"), None);
+        assert_eq!(mime_of(b"MZ"), None);
+    }
+    
+    #[test]
+    fn broken_headers_are_not_detected() {
+        // The PE header isn't where the stub says
+        let mut stub_only = executable();
+        stub_only[0x80..0x84].copy_from_slice(b"NE\0\0");
+        assert_eq!(mime_of(&stub_only), None);
+        let mut far_away = executable();
+        far_away[0x3c..0x40].copy_from_slice(&0x10000u32.to_le_bytes());
+        assert_eq!(mime_of(&far_away), None);
+        
+        let mut bad_info = bitmap();
+        bad_info[14..18].copy_from_slice(&41u32.to_le_bytes());
+        assert_eq!(mime_of(&bad_info), None);
+        
+        // No images, or the data inside the directory
+        let mut empty_icon = icon();
+        empty_icon[4] = 0;
+        assert_eq!(mime_of(&empty_icon), None);
+        let mut overlapping_icon = icon();
+        overlapping_icon[18] = 6;
+        assert_eq!(mime_of(&overlapping_icon), None);
+        assert_eq!(mime_of(&header(b"\0\0\x01\0", 64, &[])), None);
+    }
+    
+    #[test]
+    fn mp3_frames_must_be_valid_and_followed() {
+        // Free-format and reserved bitrates, and a reserved sample rate
+        assert_eq!(mime_of(&header(b"\xff\xfb\x00\0", HEADER_LEN, &[])), None);
+        assert_eq!(mime_of(&header(b"\xff\xfb\xf0\0", HEADER_LEN, &[])), None);
+        assert_eq!(mime_of(&header(b"\xff\xfb\x9c\0", HEADER_LEN, &[(417, b"\xff\xfb")])), None);
+        
+        // No second frame where the first one ends
+        let mut unfollowed = mp3();
+        unfollowed[417] = 0;
+        assert_eq!(mime_of(&unfollowed), None);
+        // The data ends before the first frame does
+        assert_eq!(mime_of(b"\xff\xfb\x90\0"), None);
+        
+        // MPEG-2 at 64 kbit/s and 22.05 kHz, with a padding byte: 209 bytes
+        let mpeg2 = header(b"\xff\xf3\x82\0", HEADER_LEN, &[(209, b"\xff\xf3")]);
+        assert_eq!(mime_of(&mpeg2), Some("audio/mpeg"));
+    }
+    
+    #[test]
+    fn kinds_have_aliases() {
+        assert_eq!(mime_for_kind("jpg"), Some("image/jpeg"));
+        assert_eq!(mime_for_kind("JPEG"), Some("image/jpeg"));
+        assert_eq!(mime_for_kind("exe"), Some("application/vnd.microsoft.portable-executable"));
+        assert_eq!(mime_for_kind("doc"), None);
+        
+        let kinds = known_kinds();
+        assert!(kinds.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(kinds.contains(&"bmp") && !kinds.contains(&"jpg"));
+    }
+}
@@ -0,0 +1,611 @@
+use std::ffi::OsStr;
+use std::path::Path;
+
+use crate::config::{FileKind, MatchTarget, SearchConfig};
+use crate::escape::{parse_query, unescape_query, QueryPart};
+use crate::expression::{Entry, Predicate};
+use crate::permissions::PermissionFilter;
+use crate::unicode::{normalize, prepare, push_folded, push_unaccented, Normalization};
+
+/// A name pattern: an exact name, or a wildcard pattern using `*` and `?`.
+/// Case-insensitive patterns use full Unicode case folding. `\xNN` in the
+/// query stands for a raw byte, for names that aren't valid UTF-8, and
+/// `\*` and `\?` for a plain `*` and `?`.
+pub struct Pattern {
+    query: String,
+    use_wildcards: bool,
+    // The query as names are compared with it: folded, stripped and normalised
+    prepared: String,
+    prepared_chars: Vec<Token<char>>,
+    // The same, for comparing with names that aren't valid UTF-8
+    prepared_units: Vec<Token<Unit>>,
+    // The query asks for bytes that aren't valid UTF-8, so only such names can match
+    has_raw_bytes: bool,
+    is_wildcard: bool,
+    case_sensitive: bool,
+    ignore_accents: bool,
+    normalization: Normalization,
+}
+
+// Names that aren't valid UTF-8 are compared as characters with the odd
+// stray byte between them; a `?` stands for either
+#[derive(Clone, Copy, PartialEq)]
+enum Unit {
+    Char(char),
+    Byte(u8),
+}
+
+// One place in a wildcard pattern, so an escaped `*` is told apart from a wildcard
+#[derive(Clone, Copy, PartialEq)]
+enum Token<T> {
+    Literal(T),
+    AnyRun,
+    AnyOne,
+}
+
+impl Pattern {
+    pub fn new(query: &str, case_sensitive: bool, use_wildcards: bool) -> Self {
+        let mut pattern = Self {
+            query: query.to_string(),
+            use_wildcards,
+            prepared: String::new(),
+            prepared_chars: Vec::new(),
+            prepared_units: Vec::new(),
+            has_raw_bytes: false,
+            is_wildcard: false,
+            case_sensitive,
+            ignore_accents: false,
+            normalization: Normalization::None,
+        };
+        pattern.prepare_query();
+        pattern
+    }
+    
+    /// Also brings the query and every name to normalisation form `form`.
+    pub fn normalized(mut self, form: Normalization) -> Self {
+        self.normalization = form;
+        self.prepare_query();
+        self
+    }
+    
+    /// Compares without accents and with ligatures spelled out, so
+    /// `Resume*` matches `Résumé_final.docx`.
+    pub fn ignore_accents(mut self, ignore_accents: bool) -> Self {
+        self.ignore_accents = ignore_accents;
+        self.prepare_query();
+        self
+    }
+    
+    /// Whether the query has a `*` or `?` that isn't escaped, and wildcards are on.
+    pub fn is_wildcard(&self) -> bool {
+        self.is_wildcard
+    }
+    
+    // The literal parts are prepared one at a time, so folding or
+    // normalising can't run into a wildcard
+    fn prepare_query(&mut self) {
+        self.prepared_chars.clear();
+        self.prepared_units.clear();
+        self.has_raw_bytes = false;
+        self.is_wildcard = false;
+        
+        for part in parse_query(&self.query, self.use_wildcards) {
+            let bytes = match part {
+                QueryPart::Literal(bytes) => bytes,
+                QueryPart::AnyRun => {
+                    self.prepared_chars.push(Token::AnyRun);
+                    self.prepared_units.push(Token::AnyRun);
+                    self.is_wildcard = true;
+                    continue;
+                }
+                QueryPart::AnyOne => {
+                    self.prepared_chars.push(Token::AnyOne);
+                    self.prepared_units.push(Token::AnyOne);
+                    self.is_wildcard = true;
+                    continue;
+                }
+            };
+            
+            self.prepared_units.extend(self.prepare_units(&bytes).into_iter().map(Token::Literal));
+            match String::from_utf8(bytes) {
+                Ok(text) => {
+                    let text = prepare(&text, !self.case_sensitive, self.ignore_accents, self.normalization);
+                    self.prepared_chars.extend(text.chars().map(Token::Literal));
+                }
+                Err(_) => self.has_raw_bytes = true,
+            }
+        }
+        
+        self.prepared = self.prepared_chars.iter()
+            .filter_map(|token| match *token {
+                Token::Literal(c) => Some(c),
+                Token::AnyRun | Token::AnyOne => None,
+            })
+            .collect();
+    }
+    
+    fn prepare_units(&self, bytes: &[u8]) -> Vec<Unit> {
+        let mut units = Vec::with_capacity(bytes.len());
+        for chunk in bytes.utf8_chunks() {
+            let valid = prepare(chunk.valid(), !self.case_sensitive, self.ignore_accents, self.normalization);
+            units.extend(valid.chars().map(Unit::Char));
+            units.extend(chunk.invalid().iter().map(|&byte| Unit::Byte(byte)));
+        }
+        units
+    }
+    
+    pub fn matches(&self, text: &str) -> bool {
+        // A UTF-8 name can't hold the stray bytes the query asks for
+        if self.has_raw_bytes {
+            return false;
+        }
+        
+        let text = prepare(text, !self.case_sensitive, self.ignore_accents, self.normalization);
+        
+        if !self.is_wildcard {
+            return *text == self.prepared;
+        }
+        
+        let text_chars: Vec<char> = text.chars().collect();
+        match_wildcard(&self.prepared_chars, &text_chars)
+    }
+    
+    /// Matches a name as the file system gave it. On Unix a name that
+    /// isn't valid UTF-8 is compared byte for byte where it isn't, rather
+    /// than after replacing those bytes.
+    pub fn matches_os(&self, name: &OsStr) -> bool {
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let bytes = name.as_bytes();
+            match std::str::from_utf8(bytes) {
+                Ok(text) => self.matches(text),
+                Err(_) => match_wildcard(&self.prepared_units, &self.prepare_units(bytes)),
+            }
+        }
+        
+        #[cfg(not(unix))]
+        {
+            self.matches(&name.to_string_lossy())
+        }
+    }
+}
+
+fn match_wildcard<T: Copy + PartialEq>(pattern: &[Token<T>], text: &[T]) -> bool {
+    let mut p = 0;
+    let mut t = 0;
+    let mut text_idx = 0;
+    let mut star_idx = None;
+    
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == Token::AnyOne || pattern[p] == Token::Literal(text[t])) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == Token::AnyRun {
+            star_idx = Some(p);
+            text_idx = t;
+            p += 1;
+        } else if let Some(si) = star_idx {
+            p = si + 1;
+            t = text_idx + 1;
+            text_idx += 1;
+        } else {
+            return false;
+        }
+    }
+    
+    while p < pattern.len() && pattern[p] == Token::AnyRun {
+        p += 1;
+    }
+    
+    p == pattern.len()
+}
+
+// Fuzzy scoring weights, in the spirit of fzf: every matched character
+// earns points, gaps cost a little, and characters that start a word or
+// continue a run of matches earn a bonus
+const SCORE_MATCH: i32 = 16;
+const PENALTY_GAP_START: i32 = 3;
+const PENALTY_GAP_EXTENSION: i32 = 1;
+const BONUS_BOUNDARY: i32 = 8;
+const BONUS_CAMEL_CASE: i32 = 7;
+const BONUS_CONSECUTIVE: i32 = 4;
+
+/// A fuzzy query: a name matches if it contains the query's characters in
+/// order, and scores higher the more tightly and meaningfully they line up
+/// (`fsr` fits `filesearch.rs` better than `fast_user_report`).
+pub struct FuzzyPattern {
+    query: String,
+    chars: Vec<char>,
+    case_sensitive: bool,
+    ignore_accents: bool,
+    normalization: Normalization,
+}
+
+impl FuzzyPattern {
+    pub fn new(query: &str, case_sensitive: bool) -> Self {
+        let mut pattern = Self {
+            query: query.to_string(),
+            chars: Vec::new(),
+            case_sensitive,
+            ignore_accents: false,
+            normalization: Normalization::None,
+        };
+        pattern.prepare_query();
+        pattern
+    }
+    
+    /// Also brings the query and every name to normalisation form `form`.
+    pub fn normalized(mut self, form: Normalization) -> Self {
+        self.normalization = form;
+        self.prepare_query();
+        self
+    }
+    
+    /// Compares without accents and with ligatures spelled out.
+    pub fn ignore_accents(mut self, ignore_accents: bool) -> Self {
+        self.ignore_accents = ignore_accents;
+        self.prepare_query();
+        self
+    }
+    
+    fn prepare_query(&mut self) {
+        // Names are scored as text, so stray bytes become replacement characters on both sides
+        let query = String::from_utf8_lossy(&unescape_query(&self.query)).into_owned();
+        self.chars = prepare(&query, !self.case_sensitive, self.ignore_accents, self.normalization).chars().collect();
+    }
+    
+    pub fn matches(&self, text: &str) -> bool {
+        self.score(text).is_some()
+    }
+    
+    /// How well `text` fits, higher being better; `None` if it doesn't.
+    /// An empty query fits everything with a score of 0.
+    pub fn score(&self, text: &str) -> Option<i32> {
+        if self.chars.is_empty() {
+            return Some(0);
+        }
+        
+        let original: Vec<char> = normalize(text, self.normalization).chars().collect();
+        
+        // Folding can turn one character into several (ß into ss) and
+        // stripping accents into none, so keep track of which original
+        // character each compared one came from
+        let mut text = Vec::with_capacity(original.len());
+        let mut origin = Vec::with_capacity(original.len());
+        let mut folded = String::new();
+        let mut stripped = String::new();
+        for (index, &c) in original.iter().enumerate() {
+            folded.clear();
+            if self.case_sensitive {
+                folded.push(c);
+            } else {
+                push_folded(c, &mut folded);
+            }
+            
+            let compared = if self.ignore_accents {
+                stripped.clear();
+                for f in folded.chars() {
+                    push_unaccented(f, &mut stripped);
+                }
+                &stripped
+            } else {
+                &folded
+            };
+            for f in compared.chars() {
+                text.push(f);
+                origin.push(index);
+            }
+        }
+        
+        // The first place the whole query fits...
+        let mut next = 0;
+        let mut end = None;
+        for (i, &c) in text.iter().enumerate() {
+            if c == self.chars[next] {
+                next += 1;
+                if next == self.chars.len() {
+                    end = Some(i);
+                    break;
+                }
+            }
+        }
+        let end = end?;
+        
+        // ...narrowed by matching backwards from its end
+        let mut remaining = self.chars.len();
+        let mut start = end;
+        for i in (0..=end).rev() {
+            if text[i] == self.chars[remaining - 1] {
+                remaining -= 1;
+                if remaining == 0 {
+                    start = i;
+                    break;
+                }
+            }
+        }
+        
+        let mut score = 0;
+        let mut next = 0;
+        let mut previous_match = None;
+        
+        for (i, &c) in text.iter().enumerate().take(end + 1).skip(start) {
+            if next < self.chars.len() && c == self.chars[next] {
+                let mut bonus = boundary_bonus(&original, origin[i]);
+                if previous_match == Some(i.wrapping_sub(1)) {
+                    bonus = bonus.max(BONUS_CONSECUTIVE);
+                }
+                // Where the match starts matters most
+                if next == 0 {
+                    bonus *= 2;
+                }
+                score += SCORE_MATCH + bonus;
+                previous_match = Some(i);
+                next += 1;
+            } else if previous_match == Some(i.wrapping_sub(1)) {
+                score -= PENALTY_GAP_START;
+            } else {
+                score -= PENALTY_GAP_EXTENSION;
+            }
+        }
+        
+        Some(score)
+    }
+}
+
+fn boundary_bonus(text: &[char], i: usize) -> i32 {
+    let current = text[i];
+    let previous = match i.checked_sub(1) {
+        Some(previous) => text[previous],
+        None => return BONUS_BOUNDARY,
+    };
+    
+    if matches!(previous, '/' | '\\' | '_' | '-' | '.' | ' ') {
+        BONUS_BOUNDARY
+    } else if (previous.is_lowercase() && current.is_uppercase())
+        || (!previous.is_numeric() && current.is_numeric())
+    {
+        BONUS_CAMEL_CASE
+    } else {
+        0
+    }
+}
+
+/// The query and its alternatives, plus the filters. A path matches if
+/// the part of it the search looks at fits any of the queries (or the
+/// expression, if there is one) and it passes every filter.
+pub(crate) struct PatternSet {
+    queries: Queries,
+    expression: Option<Predicate>,
+    target: MatchTarget,
+    extensions: Vec<Pattern>,
+    kinds: Vec<FileKind>,
+    only_empty: bool,
+    only_executable: bool,
+    user: Option<u32>,
+    group: Option<u32>,
+    no_user: bool,
+    no_group: bool,
+    permissions: Option<PermissionFilter>,
+    mime_types: Vec<Pattern>,
+}
+
+enum Queries {
+    Exact(Vec<Pattern>),
+    Fuzzy(Vec<FuzzyPattern>),
+}
+
+impl PatternSet {
+    pub(crate) fn new(config: &SearchConfig) -> Self {
+        let queries = if config.fuzzy {
+            Queries::Fuzzy(config.queries()
+                .map(|query| {
+                    FuzzyPattern::new(query, config.case_sensitive_for(query))
+                        .normalized(config.normalization)
+                        .ignore_accents(config.ignore_accents)
+                })
+                .collect())
+        } else {
+            Queries::Exact(config.queries()
+                .map(|query| {
+                    Pattern::new(query, config.case_sensitive_for(query), config.use_wildcards)
+                        .normalized(config.normalization)
+                        .ignore_accents(config.ignore_accents)
+                })
+                .collect())
+        };
+        
+        // At least one character before the dot, so .bashrc has no extension
+        let extensions = config.extensions.iter()
+            .map(|extension| {
+                Pattern::new(&format!("?*.{}", extension), false, true)
+                    .normalized(config.normalization)
+                    .ignore_accents(config.ignore_accents)
+            })
+            .collect();
+        
+        Self {
+            queries,
+            expression: config.expression.as_ref().map(|expression| expression.compile(config)),
+            target: config.match_target,
+            extensions,
+            kinds: config.file_kinds.clone(),
+            only_empty: config.only_empty,
+            only_executable: config.only_executable,
+            user: config.user,
+            group: config.group,
+            no_user: config.no_user,
+            no_group: config.no_group,
+            permissions: config.permissions,
+            mime_types: config.mime_types.iter().map(|mime| Pattern::new(mime, false, true)).collect(),
+        }
+    }
+    
+    /// `Some` if `path` matches, holding its best score in a fuzzy search.
+    /// `root` is the start directory it was found under, if any. The
+    /// filters that need the file's metadata or contents are checked last.
+    pub(crate) fn check(&self, path: &Path, root: Option<&Path>, kind: FileKind) -> Option<Option<i32>> {
+        if !self.kinds.is_empty() && !self.kinds.contains(&kind) {
+            return None;
+        }
+        if !self.extensions.is_empty() {
+            let name = path.file_name()?;
+            if !self.extensions.iter().any(|extension| extension.matches_os(name)) {
+                return None;
+            }
+        }
+        
+        let entry = Entry::new(path, root, kind);
+        let score = match self.expression {
+            Some(ref expression) => expression.evaluate(&entry).then_some(None)?,
+            None => self.check_queries(path, root)?,
+        };
+        
+        if !self.passes_metadata_filters(&entry) {
+            return None;
+        }
+        // Reading the start of the file costs the most, so it goes last
+        if !self.mime_types.is_empty()
+            && !entry.mime().is_some_and(|mime| self.mime_types.iter().any(|pattern| pattern.matches(mime)))
+        {
+            return None;
+        }
+        Some(score)
+    }
+    
+    fn passes_metadata_filters(&self, entry: &Entry) -> bool {
+        (!self.only_empty || entry.is_empty())
+            && (!self.only_executable || entry.is_executable())
+            && self.user.is_none_or(|uid| entry.uid() == Some(uid))
+            && self.group.is_none_or(|gid| entry.gid() == Some(gid))
+            && (!self.no_user || entry.has_unknown_user())
+            && (!self.no_group || entry.has_unknown_group())
+            && self.permissions.is_none_or(|filter| entry.mode().is_some_and(|mode| filter.matches(mode)))
+    }
+    
+    fn check_queries(&self, path: &Path, root: Option<&Path>) -> Option<Option<i32>> {
+        let text = self.target.select(path, root)?;
+        match self.queries {
+            Queries::Exact(ref patterns) => patterns.iter()
+                .any(|pattern| pattern.matches_os(text))
+                .then_some(None),
+            Queries::Fuzzy(ref patterns) => patterns.iter()
+                .filter_map(|pattern| pattern.score(&text.to_string_lossy()))
+                .max()
+                .map(Some),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn composed_query_matches_decomposed_name() {
+        let composed = Pattern::new("café.txt", true, true);
+        assert!(!composed.matches("cafe\u{301}.txt"));
+        
+        for form in [Normalization::Nfc, Normalization::Nfd] {
+            let composed = Pattern::new("café.txt", true, true).normalized(form);
+            assert!(composed.matches("cafe\u{301}.txt"));
+            assert!(composed.matches("café.txt"));
+            
+            let decomposed = Pattern::new("cafe\u{301}*", false, true).normalized(form);
+            assert!(decomposed.matches("CAFÉ.txt"));
+            assert!(decomposed.matches("Cafe\u{301} menu.pdf"));
+        }
+    }
+    
+    #[test]
+    fn wildcards_count_characters_not_code_points() {
+        let pattern = Pattern::new("caf?.txt", true, true).normalized(Normalization::Nfc);
+        assert!(pattern.matches("cafe\u{301}.txt"));
+    }
+    
+    #[test]
+    fn accents_are_ignored_in_either_form() {
+        let pattern = Pattern::new("resume*", false, true).ignore_accents(true);
+        assert!(pattern.matches("Résumé_final.docx"));
+        assert!(pattern.matches("Re\u{301}sume\u{301}_final.docx"));
+        assert!(!Pattern::new("resume*", false, true).matches("Résumé_final.docx"));
+    }
+    
+    #[test]
+    fn escaped_wildcards_are_plain_characters() {
+        let star = Pattern::new("a\\*c", true, true);
+        assert!(!star.is_wildcard());
+        assert!(star.matches("a*c"));
+        assert!(!star.matches("abc"));
+        
+        let question = Pattern::new("what\\?.txt", true, true);
+        assert!(!question.is_wildcard());
+        assert!(question.matches("what?.txt"));
+        assert!(!question.matches("whats.txt"));
+        
+        // An escaped one next to a real one
+        let mixed = Pattern::new("\\**", true, true);
+        assert!(mixed.is_wildcard());
+        assert!(mixed.matches("*.txt"));
+        assert!(!mixed.matches("a.txt"));
+    }
+    
+    #[test]
+    fn wildcards_can_be_turned_off() {
+        let pattern = Pattern::new("a*c", true, false);
+        assert!(!pattern.is_wildcard());
+        assert!(pattern.matches("a*c"));
+        assert!(!pattern.matches("abc"));
+    }
+    
+    #[test]
+    fn other_backslashes_are_kept() {
+        assert!(Pattern::new("C:\\dir", true, true).matches("C:\\dir"));
+        assert!(Pattern::new("C:\\\\dir", true, true).matches("C:\\\\dir"));
+        assert!(!Pattern::new("C:\\\\dir", true, true).matches("C:\\dir"));
+        // A backslash right before a wildcard is written as a byte
+        assert!(Pattern::new("\\\\server\\x5c*", true, true).matches("\\\\server\\share"));
+        assert!(!Pattern::new("\\\\server\\*", true, true).matches("\\\\server\\share"));
+        // Too short or not hex, so not a byte
+        assert!(Pattern::new("\\x4", true, true).matches("\\x4"));
+        assert!(Pattern::new("\\xzz", true, true).matches("\\xzz"));
+    }
+    
+    #[test]
+    fn byte_escapes_stand_for_bytes() {
+        assert!(Pattern::new("\\x41.txt", true, true).matches("A.txt"));
+        assert!(Pattern::new("caf\\xc3\\xa9", true, true).matches("café"));
+        
+        // Not valid UTF-8, so no UTF-8 name can match
+        let raw = Pattern::new("caf\\xe9*", true, true);
+        assert!(!raw.matches("café.txt"));
+        
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let name = OsStr::from_bytes(b"caf\xe9.txt");
+            assert!(raw.matches_os(name));
+            assert!(Pattern::new("caf\\xe9.txt", true, false).matches_os(name));
+            assert!(Pattern::new("caf?.txt", true, true).matches_os(name));
+            assert!(!Pattern::new("caf\\?.txt", true, true).matches_os(name));
+        }
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn shown_paths_can_be_searched_for() {
+        use std::os::unix::ffi::OsStrExt;
+        for name in [&b"caf\xe9.txt"[..], b"back\\slash", b"\\x41", b"line\nbreak"] {
+            let name = OsStr::from_bytes(name);
+            let shown = crate::escape::display_path(Path::new(name));
+            assert!(Pattern::new(&shown, true, true).matches_os(name), "{}", shown);
+        }
+    }
+    
+    #[test]
+    fn fuzzy_queries_are_normalised_too() {
+        let pattern = FuzzyPattern::new("café", true).normalized(Normalization::Nfc);
+        assert!(pattern.matches("my cafe\u{301} notes"));
+        assert!(!FuzzyPattern::new("café", true).matches("my cafe\u{301} notes"));
+    }
+}
@@ -0,0 +1,206 @@
+//! Permission bits filters, in the style of `find -perm`.
+
+use std::fmt;
+
+/// How the file's permission bits are compared with the filter's.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PermissionMatch {
+    /// Exactly these bits (`0644`)
+    Exact,
+    /// At least all of these bits (`-4000`)
+    All,
+    /// At least one of these bits (`/o+w`)
+    Any,
+}
+
+/// A `--perm` filter: permission bits, given in octal or symbolically
+/// (`u+s`, `go=rx`), and how to compare them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PermissionFilter {
+    mode: u32,
+    how: PermissionMatch,
+}
+
+impl PermissionFilter {
+    /// Parses a mode like `644`, `-4000`, `/o+w` or `-u=rwx,g+s`. A leading
+    /// `-` asks for all of the bits and `/` for any of them; otherwise the
+    /// bits must match exactly.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (how, mode) = match text.as_bytes().first() {
+            Some(b'-') => (PermissionMatch::All, &text[1..]),
+            Some(b'/') => (PermissionMatch::Any, &text[1..]),
+            _ => (PermissionMatch::Exact, text),
+        };
+        
+        let mode = if !mode.is_empty() && mode.bytes().all(|byte| byte.is_ascii_digit()) {
+            u32::from_str_radix(mode, 8)
+                .ok()
+                .filter(|&mode| mode <= 0o7777)
+                .ok_or_else(|| format!("invalid octal mode '{}'", mode))?
+        } else {
+            parse_symbolic(mode)?
+        };
+        
+        Ok(Self { mode, how })
+    }
+    
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+    
+    pub fn how(&self) -> PermissionMatch {
+        self.how
+    }
+    
+    /// Whether a file with these permission bits passes. Like `find`,
+    /// `/000` matches everything.
+    pub fn matches(&self, mode: u32) -> bool {
+        let mode = mode & 0o7777;
+        match self.how {
+            PermissionMatch::Exact => mode == self.mode,
+            PermissionMatch::All => mode & self.mode == self.mode,
+            PermissionMatch::Any => self.mode == 0 || mode & self.mode != 0,
+        }
+    }
+}
+
+/// Writes the filter back in octal, which [`PermissionFilter::parse`] accepts.
+impl fmt::Display for PermissionFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let prefix = match self.how {
+            PermissionMatch::Exact => "",
+            PermissionMatch::All => "-",
+            PermissionMatch::Any => "/",
+        };
+        write!(f, "{}{:04o}", prefix, self.mode)
+    }
+}
+
+// Clauses like `u+s` or `go=rx`, separated by commas and applied to an
+// empty mode in turn, as chmod would
+fn parse_symbolic(text: &str) -> Result<u32, String> {
+    let invalid = || format!("invalid mode '{}' (use octal like 4000 or symbols like u+s,o+w)", text);
+    let mut mode = 0;
+    
+    for clause in text.split(',') {
+        let operator_at = clause.find(['+', '-', '=']).ok_or_else(invalid)?;
+        let (who, rest) = clause.split_at(operator_at);
+        
+        let mut users = 0;
+        for letter in who.chars() {
+            users |= match letter {
+                'u' => 0o4700,
+                'g' => 0o2070,
+                'o' => 0o1007,
+                'a' => 0o7777,
+                _ => return Err(invalid()),
+            };
+        }
+        if users == 0 {
+            users = 0o7777;
+        }
+        
+        let mut bits = 0;
+        for letter in rest[1..].chars() {
+            bits |= match letter {
+                'r' => 0o444,
+                'w' => 0o222,
+                'x' => 0o111,
+                's' => 0o6000,
+                't' => 0o1000,
+                _ => return Err(invalid()),
+            };
+        }
+        let bits = bits & users;
+        
+        match rest.as_bytes()[0] {
+            b'+' => mode |= bits,
+            b'-' => mode &= !bits,
+            _ => mode = (mode & !users) | bits,
+        }
+    }
+    
+    Ok(mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn filter(text: &str) -> PermissionFilter {
+        PermissionFilter::parse(text).unwrap()
+    }
+    
+    #[test]
+    fn octal_modes_match_exactly() {
+        let exact = filter("644");
+        assert_eq!((exact.mode(), exact.how()), (0o644, PermissionMatch::Exact));
+        assert!(exact.matches(0o644));
+        assert!(exact.matches(0o100644));
+        assert!(!exact.matches(0o664));
+        assert!(!exact.matches(0o4644));
+        assert_eq!(filter("0755").mode(), 0o755);
+        assert_eq!(filter("4000").mode(), 0o4000);
+    }
+    
+    #[test]
+    fn symbolic_modes_are_applied_in_turn() {
+        assert_eq!(filter("u+s").mode(), 0o4000);
+        assert_eq!(filter("g+s").mode(), 0o2000);
+        assert_eq!(filter("o+t").mode(), 0o1000);
+        assert_eq!(filter("go=rx").mode(), 0o055);
+        assert_eq!(filter("u=rwx,g+rx,o+r").mode(), 0o754);
+        assert_eq!(filter("a+w").mode(), 0o222);
+        // No one named means everyone
+        assert_eq!(filter("+x").mode(), 0o111);
+        assert_eq!(filter("a+rwx,o-rwx").mode(), 0o770);
+        assert_eq!(filter("a+rwx,g=r").mode(), 0o747);
+    }
+    
+    #[test]
+    fn a_dash_needs_every_bit() {
+        let all = filter("-4000");
+        assert_eq!(all.how(), PermissionMatch::All);
+        assert!(all.matches(0o4755));
+        assert!(!all.matches(0o755));
+        
+        let all = filter("-u=rw,g+w");
+        assert!(all.matches(0o664));
+        assert!(all.matches(0o777));
+        assert!(!all.matches(0o644));
+    }
+    
+    #[test]
+    fn a_slash_needs_any_bit() {
+        let any = filter("/o+w");
+        assert_eq!(any.how(), PermissionMatch::Any);
+        assert!(any.matches(0o602));
+        assert!(!any.matches(0o775));
+        
+        let any = filter("/u+s,g+s");
+        assert!(any.matches(0o2755));
+        assert!(any.matches(0o4755));
+        assert!(!any.matches(0o1755));
+        
+        // As in find, no bits at all matches everything
+        assert!(filter("/000").matches(0o644));
+        assert!(filter("-000").matches(0));
+    }
+    
+    #[test]
+    fn bad_modes_are_errors() {
+        for text in ["", "-", "/", "8", "77777", "u", "u+z", "q+r", "u+r,"] {
+            assert!(PermissionFilter::parse(text).is_err(), "{:?} was accepted", text);
+        }
+    }
+    
+    #[test]
+    fn filters_are_written_back_in_octal() {
+        for text in ["644", "-4000", "/o+w", "-u=rwx,g+s"] {
+            let parsed = filter(text);
+            assert_eq!(filter(&parsed.to_string()), parsed);
+        }
+        assert_eq!(filter("/o+w").to_string(), "/0002");
+        assert_eq!(filter("u=rwx,g+s").to_string(), "2700");
+    }
+}
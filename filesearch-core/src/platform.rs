@@ -0,0 +1,29 @@
+//! The platform the engine was built for, named the way users know it.
+
+use std::env::consts;
+
+/// The CPU architecture, e.g. "x64", "ARM64", or "M-series (ARM64)" on Apple silicon.
+pub fn arch_name() -> &'static str {
+    match (consts::ARCH, consts::OS) {
+        ("aarch64", "macos") => "M-series (ARM64)",
+        ("aarch64", _) => "ARM64",
+        ("x86_64", _) => "x64",
+        (arch, _) => arch,
+    }
+}
+
+/// The operating system, e.g. "Windows", "Linux" or "macOS".
+pub fn os_name() -> &'static str {
+    match consts::OS {
+        "macos" => "macOS",
+        "linux" => "Linux",
+        "windows" => "Windows",
+        "freebsd" => "FreeBSD",
+        os => os,
+    }
+}
+
+/// Both together, as on the `System:` line of reports: "x64 Linux".
+pub fn system_description() -> String {
+    format!("{} {}", arch_name(), os_name())
+}
@@ -0,0 +1,445 @@
+use std::borrow::Cow;
+
+use crate::unicode_tables::{CANONICAL_COMPOSITION, CANONICAL_DECOMPOSITION, CASE_FOLDING, COMBINING_CLASS, NONSPACING_MARKS};
+
+/// A Unicode normalisation form to bring names and queries to before
+/// comparing them, so an `é` typed as one character matches one stored as
+/// `e` plus a combining accent (as macOS tends to create them).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Normalization {
+    None,
+    /// Composed characters where possible.
+    Nfc,
+    /// Everything decomposed into base characters and combining marks.
+    Nfd,
+}
+
+// Hangul syllables decompose by arithmetic rather than by table
+const HANGUL_BASE: u32 = 0xAC00;
+const HANGUL_COUNT: u32 = 11172;
+const JAMO_L_BASE: u32 = 0x1100;
+const JAMO_V_BASE: u32 = 0x1161;
+const JAMO_T_BASE: u32 = 0x11A7;
+const JAMO_V_COUNT: u32 = 21;
+const JAMO_T_COUNT: u32 = 28;
+const JAMO_N_COUNT: u32 = JAMO_V_COUNT * JAMO_T_COUNT;
+
+/// Full Unicode case folding, so `ß`, `SS` and `ss` all compare equal and
+/// `-i` doesn't depend on whether the query has wildcards.
+pub(crate) fn fold_case(text: &str) -> Cow<'_, str> {
+    if text.is_ascii() {
+        if text.bytes().any(|b| b.is_ascii_uppercase()) {
+            return Cow::Owned(text.to_ascii_lowercase());
+        }
+        return Cow::Borrowed(text);
+    }
+    
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars() {
+        push_folded(c, &mut folded);
+    }
+    Cow::Owned(folded)
+}
+
+pub(crate) fn push_folded(c: char, out: &mut String) {
+    match CASE_FOLDING.binary_search_by_key(&c, |&(from, _)| from) {
+        Ok(index) => out.push_str(CASE_FOLDING[index].1),
+        Err(_) => out.push(c),
+    }
+}
+
+/// Whether `text` has an uppercase letter, for smart case.
+pub(crate) fn has_uppercase(text: &str) -> bool {
+    text.chars().any(char::is_uppercase)
+}
+
+pub(crate) fn normalize(text: &str, form: Normalization) -> Cow<'_, str> {
+    // ASCII is the same in every form
+    if form == Normalization::None || text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+    
+    let decomposed = decompose(text);
+    match form {
+        Normalization::Nfc => Cow::Owned(compose(&decomposed).into_iter().collect()),
+        _ => Cow::Owned(decomposed.into_iter().collect()),
+    }
+}
+
+/// Drops accents and other nonspacing marks and spells out Latin
+/// ligatures, so `Résumé` compares equal to `Resume` and `Æble` to `AEble`.
+pub(crate) fn strip_accents(text: &str) -> Cow<'_, str> {
+    if text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+    
+    let mut stripped = String::with_capacity(text.len());
+    for c in text.chars() {
+        push_unaccented(c, &mut stripped);
+    }
+    Cow::Owned(stripped)
+}
+
+pub(crate) fn push_unaccented(c: char, out: &mut String) {
+    if c.is_ascii() {
+        out.push(c);
+        return;
+    }
+    
+    let mut decomposed = Vec::new();
+    push_decomposed(c, &mut decomposed);
+    for part in decomposed {
+        if is_nonspacing_mark(part) {
+            continue;
+        }
+        match unligature(part) {
+            Some(spelled) => out.push_str(spelled),
+            None => out.push(part),
+        }
+    }
+}
+
+// Ligatures, and letters with a stroke through them, which have no
+// decomposition to take the accent off
+fn unligature(c: char) -> Option<&'static str> {
+    Some(match c {
+        'Æ' => "AE",
+        'æ' => "ae",
+        'Œ' => "OE",
+        'œ' => "oe",
+        'Ĳ' => "IJ",
+        'ĳ' => "ij",
+        'ß' => "ss",
+        'ẞ' => "SS",
+        'ﬀ' => "ff",
+        'ﬁ' => "fi",
+        'ﬂ' => "fl",
+        'ﬃ' => "ffi",
+        'ﬄ' => "ffl",
+        'ﬅ' | 'ﬆ' => "st",
+        'Ø' => "O",
+        'ø' => "o",
+        'Ł' => "L",
+        'ł' => "l",
+        'Đ' => "D",
+        'đ' => "d",
+        'Ħ' => "H",
+        'ħ' => "h",
+        _ => return None,
+    })
+}
+
+/// Brings a name or query to the form it's compared in: case folded if
+/// `fold`, stripped of accents if `unaccent`, then normalised. Folding
+/// goes through NFD first, as Unicode's canonical caseless matching does.
+pub(crate) fn prepare(text: &str, fold: bool, unaccent: bool, form: Normalization) -> Cow<'_, str> {
+    // ASCII has no accents to strip
+    if unaccent && !text.is_ascii() {
+        // Accents come off after folding, since folding can add some (İ)
+        let folded = if fold {
+            fold_case(&normalize(text, Normalization::Nfd)).into_owned()
+        } else {
+            text.to_string()
+        };
+        let stripped = strip_accents(&folded).into_owned();
+        return Cow::Owned(normalize(&stripped, form).into_owned());
+    }
+    
+    match (fold, form) {
+        (false, _) => normalize(text, form),
+        (true, Normalization::None) => fold_case(text),
+        (true, _) => {
+            let decomposed = normalize(text, Normalization::Nfd);
+            let folded = fold_case(&decomposed).into_owned();
+            Cow::Owned(normalize(&folded, form).into_owned())
+        }
+    }
+}
+
+fn combining_class(c: char) -> u8 {
+    if c.is_ascii() {
+        return 0;
+    }
+    
+    match COMBINING_CLASS.binary_search_by(|&(first, last, _)| {
+        if last < c {
+            std::cmp::Ordering::Less
+        } else if first > c {
+            std::cmp::Ordering::Greater
+        } else {
+            std::cmp::Ordering::Equal
+        }
+    }) {
+        Ok(index) => COMBINING_CLASS[index].2,
+        Err(_) => 0,
+    }
+}
+
+fn is_nonspacing_mark(c: char) -> bool {
+    NONSPACING_MARKS.binary_search_by(|&(first, last)| {
+        if last < c {
+            std::cmp::Ordering::Less
+        } else if first > c {
+            std::cmp::Ordering::Greater
+        } else {
+            std::cmp::Ordering::Equal
+        }
+    }).is_ok()
+}
+
+fn push_decomposed(c: char, out: &mut Vec<char>) {
+    let code = c as u32;
+    if (HANGUL_BASE..HANGUL_BASE + HANGUL_COUNT).contains(&code) {
+        let index = code - HANGUL_BASE;
+        out.push(char::from_u32(JAMO_L_BASE + index / JAMO_N_COUNT).unwrap_or(c));
+        out.push(char::from_u32(JAMO_V_BASE + (index % JAMO_N_COUNT) / JAMO_T_COUNT).unwrap_or(c));
+        if !index.is_multiple_of(JAMO_T_COUNT) {
+            out.push(char::from_u32(JAMO_T_BASE + index % JAMO_T_COUNT).unwrap_or(c));
+        }
+        return;
+    }
+    
+    match CANONICAL_DECOMPOSITION.binary_search_by_key(&c, |&(from, _)| from) {
+        Ok(index) => {
+            for part in CANONICAL_DECOMPOSITION[index].1.chars() {
+                push_decomposed(part, out);
+            }
+        }
+        Err(_) => out.push(c),
+    }
+}
+
+/// Full canonical decomposition with combining marks put in canonical order.
+fn decompose(text: &str) -> Vec<char> {
+    let mut chars = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        push_decomposed(c, &mut chars);
+    }
+    
+    // Each run of marks is sorted by class; the sort is stable, so marks of
+    // the same class keep their order
+    let mut start = 0;
+    while start < chars.len() {
+        if combining_class(chars[start]) == 0 {
+            start += 1;
+            continue;
+        }
+        let mut end = start;
+        while end < chars.len() && combining_class(chars[end]) != 0 {
+            end += 1;
+        }
+        chars[start..end].sort_by_key(|&c| combining_class(c));
+        start = end;
+    }
+    
+    chars
+}
+
+fn compose_pair(first: char, second: char) -> Option<char> {
+    let (l, v) = (first as u32, second as u32);
+    
+    // Leading and vowel jamo make an LV syllable...
+    if (JAMO_L_BASE..JAMO_L_BASE + 19).contains(&l) && (JAMO_V_BASE..JAMO_V_BASE + JAMO_V_COUNT).contains(&v) {
+        return char::from_u32(HANGUL_BASE + ((l - JAMO_L_BASE) * JAMO_V_COUNT + (v - JAMO_V_BASE)) * JAMO_T_COUNT);
+    }
+    // ...and an LV syllable with a trailing jamo an LVT one
+    if (HANGUL_BASE..HANGUL_BASE + HANGUL_COUNT).contains(&l)
+        && (l - HANGUL_BASE).is_multiple_of(JAMO_T_COUNT)
+        && (JAMO_T_BASE + 1..JAMO_T_BASE + JAMO_T_COUNT).contains(&v)
+    {
+        return char::from_u32(l + (v - JAMO_T_BASE));
+    }
+    
+    CANONICAL_COMPOSITION.binary_search_by_key(&(first, second), |&(a, b, _)| (a, b))
+        .ok()
+        .map(|index| CANONICAL_COMPOSITION[index].2)
+}
+
+/// Canonical composition of already decomposed text.
+fn compose(chars: &[char]) -> Vec<char> {
+    let mut result: Vec<char> = Vec::with_capacity(chars.len());
+    // Where the last starter is in `result`, and the class of the last
+    // character that didn't combine with it
+    let mut starter: Option<usize> = None;
+    let mut last_class: Option<u8> = None;
+    
+    for &c in chars {
+        let class = combining_class(c);
+        
+        if let Some(index) = starter {
+            // A mark is blocked by an uncombined mark of the same or higher class
+            let blocked = match last_class {
+                None => false,
+                Some(last) => last == 0 || last >= class,
+            };
+            if !blocked {
+                if let Some(composed) = compose_pair(result[index], c) {
+                    result[index] = composed;
+                    continue;
+                }
+            }
+        }
+        
+        if class == 0 {
+            starter = Some(result.len());
+            last_class = None;
+        } else {
+            last_class = Some(class);
+        }
+        result.push(c);
+    }
+    
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn tables_are_sorted_for_binary_search() {
+        assert!(CASE_FOLDING.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(CANONICAL_DECOMPOSITION.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(CANONICAL_COMPOSITION.windows(2).all(|pair| (pair[0].0, pair[0].1) < (pair[1].0, pair[1].1)));
+    }
+    
+    #[test]
+    fn ascii_folds_without_copying() {
+        assert!(matches!(fold_case("report.txt"), Cow::Borrowed("report.txt")));
+        assert_eq!(fold_case("README.md"), "readme.md");
+    }
+    
+    #[test]
+    fn sharp_s_folds_to_ss() {
+        assert_eq!(fold_case("Straße"), "strasse");
+        assert_eq!(fold_case("STRASSE"), fold_case("straße"));
+        assert_eq!(fold_case("\u{1e9e}"), "ss");
+    }
+    
+    #[test]
+    fn sigma_folds_the_same_in_every_position() {
+        // Capital, medial and final sigma
+        assert_eq!(fold_case("ΟΔΟΣ"), "οδοσ");
+        assert_eq!(fold_case("οδος"), "οδοσ");
+        assert_eq!(fold_case("Σίσυφος"), fold_case("ΣΊΣΥΦΟΣ"));
+    }
+    
+    #[test]
+    fn dotted_capital_i_keeps_its_dot() {
+        // Full folding, not the Turkic mapping: İ is i plus a combining dot
+        assert_eq!(fold_case("İ"), "i\u{307}");
+        assert_eq!(fold_case("İstanbul"), "i\u{307}stanbul");
+        assert_ne!(fold_case("İstanbul"), fold_case("ISTANBUL"));
+        // ...and dotless ı has no case folding at all
+        assert_eq!(fold_case("ı"), "ı");
+        assert_eq!(fold_case("I"), "i");
+    }
+    
+    #[test]
+    fn dotted_capital_i_matches_plain_i_without_accents() {
+        assert_eq!(prepare("İstanbul", true, true, Normalization::None), "istanbul");
+        assert_eq!(prepare("ISTANBUL", true, true, Normalization::None), "istanbul");
+    }
+    
+    #[test]
+    fn other_scripts_fold() {
+        assert_eq!(fold_case("ПРИВЕТ"), "привет");
+        assert_eq!(fold_case("Ǆ"), "ǆ");
+        // Cherokee folds to the uppercase letters, unlike most scripts
+        assert_eq!(fold_case("\u{ab70}"), "\u{13a0}");
+    }
+    
+    fn nfc(text: &str) -> String {
+        normalize(text, Normalization::Nfc).into_owned()
+    }
+    
+    fn nfd(text: &str) -> String {
+        normalize(text, Normalization::Nfd).into_owned()
+    }
+    
+    #[test]
+    fn composed_and_decomposed_convert() {
+        assert_eq!(nfd("é"), "e\u{301}");
+        assert_eq!(nfc("e\u{301}"), "é");
+        assert_eq!(nfd("Å"), "A\u{30a}");
+        // Decomposition goes all the way down
+        assert_eq!(nfd("ệ"), "e\u{323}\u{302}");
+        assert_eq!(nfc("e\u{323}\u{302}"), "ệ");
+    }
+    
+    #[test]
+    fn forms_round_trip() {
+        for text in ["Café", "cafe\u{301}", "Ångström", "ệ", "e\u{302}\u{323}", "한국어", "Ω\u{2126}", "naïve résumé"] {
+            assert_eq!(nfc(&nfd(text)), nfc(text), "{}", text);
+            assert_eq!(nfd(&nfc(text)), nfd(text), "{}", text);
+            assert_eq!(nfc(&nfc(text)), nfc(text), "{}", text);
+        }
+    }
+    
+    #[test]
+    fn marks_are_put_in_canonical_order() {
+        // Dot below (220) goes before the circumflex (230) whichever was typed first
+        assert_eq!(nfd("e\u{302}\u{323}"), nfd("e\u{323}\u{302}"));
+        assert_eq!(nfc("e\u{302}\u{323}"), "ệ");
+        // A mark with nothing to combine with stays where it is
+        assert_eq!(nfc("a\u{323}\u{301}"), "\u{1ea1}\u{301}");
+    }
+    
+    #[test]
+    fn hangul_is_composed_by_arithmetic() {
+        assert_eq!(nfd("한"), "\u{1112}\u{1161}\u{11ab}");
+        assert_eq!(nfc("\u{1112}\u{1161}\u{11ab}"), "한");
+        assert_eq!(nfd("가"), "\u{1100}\u{1161}");
+    }
+    
+    #[test]
+    fn excluded_characters_stay_decomposed() {
+        // Devanagari qa is a composition exclusion, the ohm sign a singleton
+        assert_eq!(nfc("\u{958}"), "\u{915}\u{93c}");
+        assert_eq!(nfc("\u{2126}"), "\u{3a9}");
+    }
+    
+    #[test]
+    fn ascii_is_left_alone() {
+        assert!(matches!(normalize("plain.txt", Normalization::Nfd), Cow::Borrowed(_)));
+        assert!(matches!(strip_accents("plain.txt"), Cow::Borrowed(_)));
+    }
+    
+    #[test]
+    fn accents_come_off_either_form() {
+        assert_eq!(strip_accents("Résumé"), "Resume");
+        assert_eq!(strip_accents("Re\u{301}sume\u{301}"), "Resume");
+        assert_eq!(strip_accents("Ångström"), "Angstrom");
+        assert_eq!(strip_accents("ệ"), "e");
+    }
+    
+    #[test]
+    fn ligatures_and_stroked_letters_are_spelled_out() {
+        assert_eq!(strip_accents("Æble"), "AEble");
+        assert_eq!(strip_accents("Œuvre"), "OEuvre");
+        assert_eq!(strip_accents("Łódź"), "Lodz");
+        assert_eq!(strip_accents("ﬁle"), "file");
+        assert_eq!(strip_accents("Øresund"), "Oresund");
+    }
+    
+    #[test]
+    fn composed_and_decomposed_names_compare_equal_once_prepared() {
+        let composed = "Café.txt";
+        let decomposed = "Cafe\u{301}.txt";
+        assert_ne!(prepare(composed, false, false, Normalization::None), prepare(decomposed, false, false, Normalization::None));
+        for form in [Normalization::Nfc, Normalization::Nfd] {
+            assert_eq!(prepare(composed, false, false, form), prepare(decomposed, false, false, form));
+            assert_eq!(prepare("CAFÉ.TXT", true, false, form), prepare(decomposed, true, false, form));
+        }
+        assert_eq!(prepare(decomposed, true, true, Normalization::None), "cafe.txt");
+    }
+    
+    #[test]
+    fn uppercase_is_detected_beyond_ascii() {
+        assert!(has_uppercase("Report"));
+        assert!(has_uppercase("éCOLE"));
+        assert!(has_uppercase("Ωmega"));
+        assert!(!has_uppercase("straße"));
+        assert!(!has_uppercase("日本語"));
+    }
+}
@@ -0,0 +1,113 @@
+//! Reads a search a page at a time, the way the console and the legacy
+//! front-end do.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use filesearch_core::{SearchConfig, SearchConfigBuilder, SearchEngine, SearchEvent, StopReason};
+
+const MATCHES: usize = 25;
+// Enough that a worker hands matches over well before it runs out of
+// directories, so the search is still going when a page is taken
+const MANY_MATCHES: usize = 1000;
+
+// `matches` files called "target", each in a directory of its own
+fn make_tree(name: &str, matches: usize) -> PathBuf {
+    let root = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("paging").join(name);
+    let _ = fs::remove_dir_all(&root);
+    for index in 0..matches {
+        let dir = root.join(format!("d{}", index));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("target"), "x").unwrap();
+        fs::write(dir.join("other"), "x").unwrap();
+    }
+    root
+}
+
+fn config(root: &Path) -> SearchConfigBuilder {
+    SearchConfig::builder("target").start_dir(root).threads(1)
+}
+
+#[test]
+fn pages_are_full_until_the_search_runs_out() {
+    let root = make_tree("full", MATCHES);
+    let mut results = SearchEngine::new(config(&root).threads(4).build()).into_results();
+    
+    let sizes: Vec<usize> = std::iter::from_fn(|| Some(results.next_page(10).len()))
+        .take(3)
+        .collect();
+    assert_eq!(sizes, [10, 10, 5]);
+    assert_eq!(results.finish().stop_reason(), StopReason::Completed);
+}
+
+#[test]
+fn every_event_is_handed_over_in_order() {
+    let root = make_tree("events", MATCHES);
+    let mut results = SearchEngine::new(config(&root).threads(4).build()).into_results();
+    
+    let mut seen = Vec::new();
+    let mut matches = 0;
+    loop {
+        let page = results.next_page_with(10, |event| seen.push(event.clone()));
+        matches += page.len();
+        if page.len() < 10 {
+            break;
+        }
+    }
+    
+    assert_eq!(matches, MATCHES);
+    assert!(matches!(seen.first(), Some(SearchEvent::Started { .. })));
+    assert!(matches!(seen.last(), Some(SearchEvent::Finished { reason: StopReason::Completed, .. })));
+    assert_eq!(seen.iter().filter(|event| matches!(event, SearchEvent::Match(_))).count(), MATCHES);
+}
+
+#[test]
+fn stopping_after_a_page_is_not_a_cancel() {
+    let root = make_tree("stop", MANY_MATCHES);
+    let mut results = SearchEngine::new(config(&root).build()).into_results();
+    
+    assert_eq!(results.next_page(10).len(), 10);
+    results.stop();
+    
+    let mut stopping = None;
+    results.next_page_with(usize::MAX, |event| {
+        if let SearchEvent::Stopping { reason } = event {
+            stopping = Some(*reason);
+        }
+    });
+    assert_eq!(stopping, Some(StopReason::Stopped));
+    assert_eq!(results.finish().stop_reason(), StopReason::Stopped);
+}
+
+#[test]
+fn time_spent_paused_is_not_timed() {
+    let root = make_tree("timeout", MANY_MATCHES);
+    let config = config(&root).timeout(Some(Duration::from_millis(300))).build();
+    let mut results = SearchEngine::new(config).into_results();
+    
+    assert_eq!(results.next_page(10).len(), 10);
+    // Someone reading the page for longer than the whole search may take
+    thread::sleep(Duration::from_millis(600));
+    results.next_page(usize::MAX);
+    
+    assert_eq!(results.finish().stop_reason(), StopReason::Completed);
+}
+
+#[test]
+fn forgotten_matches_leave_the_results() {
+    let root = make_tree("forget", MATCHES);
+    let mut results = SearchEngine::new(config(&root).build()).into_results();
+    results.next_page(usize::MAX);
+    let mut engine = results.finish();
+    
+    // A match itself, then a directory holding one
+    assert_eq!(engine.forget(&root.join("d0").join("target")), 1);
+    assert_eq!(engine.forget(&root.join("d1")), 1);
+    assert_eq!(engine.forget(&root.join("nowhere")), 0);
+    
+    assert_eq!(engine.found_items().len(), MATCHES - 2);
+    assert_eq!(engine.stats().matches_found, MATCHES - 2);
+    assert!(!engine.found_items().iter().any(|path| path.starts_with(root.join("d1"))));
+}
@@ -0,0 +1,120 @@
+//! Interrupts a search that checkpoints as it goes, resumes it from one of
+//! those checkpoints and checks that no directory is walked twice.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use filesearch_core::{Checkpoint, SearchConfig, SearchEngine, SearchEvent};
+
+const TOP_DIRS: usize = 20;
+const SUBDIRS: usize = 20;
+
+// Every directory in the tree, the root included
+fn make_tree(root: &Path) -> HashSet<PathBuf> {
+    let _ = fs::remove_dir_all(root);
+    let mut dirs = HashSet::from([root.to_path_buf()]);
+    
+    for top in 0..TOP_DIRS {
+        let top_dir = root.join(format!("d{}", top));
+        for sub in 0..SUBDIRS {
+            let sub_dir = top_dir.join(format!("s{}", sub));
+            fs::create_dir_all(&sub_dir).unwrap();
+            fs::write(sub_dir.join("file.txt"), "x").unwrap();
+            dirs.insert(sub_dir);
+        }
+        dirs.insert(top_dir);
+    }
+    dirs
+}
+
+// A directory is only done once its subdirectories are queued, so one
+// still listed as pending can't have a child that was queued or searched
+fn assert_consistent(checkpoint: &Checkpoint) {
+    assert_eq!(checkpoint.dirs_searched, checkpoint.searched_dirs.len());
+    
+    let parents: HashSet<&Path> = checkpoint.pending_dirs.iter()
+        .chain(&checkpoint.searched_dirs)
+        .filter_map(|dir| dir.parent())
+        .collect();
+    for dir in &checkpoint.pending_dirs {
+        assert!(!checkpoint.searched_dirs.contains(dir), "{} is both pending and searched", dir.display());
+        assert!(!parents.contains(dir.as_path()), "{} is pending although it was already read", dir.display());
+    }
+}
+
+// Runs the search until half the directories are entered, checking every
+// checkpoint on the way, and returns the last one that had work left
+fn interrupted_search(tree: &Path, total_dirs: usize, checkpoint_path: &Path) -> Option<Checkpoint> {
+    let _ = fs::remove_file(checkpoint_path);
+    
+    // Nothing matches, so no matches are ever held back by a worker
+    let config = SearchConfig::builder("needle")
+        .start_dirs(vec![tree.to_path_buf()])
+        .threads(4)
+        .checkpoint_path(Some(checkpoint_path.to_path_buf()))
+        .checkpoint_interval(Duration::ZERO)
+        .build();
+    let mut engine = SearchEngine::new(config);
+    let events = engine.subscribe();
+    let search = engine.spawn();
+    
+    // Stand in for a process killed right after writing a checkpoint
+    let mut last_seen = None;
+    let mut entered = 0;
+    for event in events {
+        if let SearchEvent::DirEntered { .. } = event {
+            entered += 1;
+            if let Ok(checkpoint) = Checkpoint::load(checkpoint_path) {
+                assert_consistent(&checkpoint);
+                if !checkpoint.pending_dirs.is_empty() && !checkpoint.searched_dirs.is_empty() {
+                    last_seen = Some(checkpoint);
+                }
+            }
+            if entered == total_dirs / 2 {
+                search.cancel();
+            }
+        }
+    }
+    search.join();
+    last_seen
+}
+
+#[test]
+fn resuming_a_checkpoint_walks_each_directory_once() {
+    let tmp = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("resume");
+    let tree = tmp.join("tree");
+    let all_dirs = make_tree(&tree);
+    let checkpoint_path = tmp.join("search.ckpt");
+    
+    // The workers can get through the whole tree before the first
+    // checkpoint is written, which leaves nothing to resume; try again
+    let snapshot = (0..20)
+        .find_map(|_| interrupted_search(&tree, all_dirs.len(), &checkpoint_path))
+        .expect("no checkpoint was written while the search ran");
+    let already_searched = snapshot.searched_dirs.clone();
+    
+    let config = snapshot.apply_to(SearchConfig::builder("")).threads(4).build();
+    let mut engine = SearchEngine::new(config);
+    engine.restore(snapshot);
+    let events = engine.subscribe();
+    engine.search();
+    
+    let mut walked_again = Vec::new();
+    for event in events.try_iter() {
+        if let SearchEvent::DirEntered { path, .. } = event {
+            assert!(!already_searched.contains(&path), "{} was walked twice", path.display());
+            walked_again.push(path);
+        }
+    }
+    
+    let resumed: HashSet<PathBuf> = walked_again.iter().cloned().collect();
+    assert_eq!(resumed.len(), walked_again.len(), "a directory was walked twice after resuming");
+    let searched_parents: HashSet<&Path> = already_searched.iter().filter_map(|dir| dir.parent()).collect();
+    for dir in &resumed {
+        assert!(!searched_parents.contains(dir.as_path()),
+            "{} was walked again although its subdirectories were already searched", dir.display());
+    }
+    assert_eq!(resumed.union(&already_searched).cloned().collect::<HashSet<_>>(), all_dirs);
+}
@@ -0,0 +1,55 @@
+//! Start directories inside one another, as kept when a depth limit is set.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use filesearch_core::{Match, SearchConfig, SearchEngine, SearchMode};
+
+// a/b/c/x/target.txt, with a/b/c/x/y below it
+fn make_tree(name: &str) -> PathBuf {
+    let tmp = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("roots").join(name);
+    let _ = fs::remove_dir_all(&tmp);
+    fs::create_dir_all(tmp.join("a/b/c/x/y")).unwrap();
+    fs::write(tmp.join("a/b/c/x/target.txt"), "x").unwrap();
+    fs::write(tmp.join("a/b/c/x/y/target.txt"), "x").unwrap();
+    tmp
+}
+
+fn search(roots: &[PathBuf], max_depth: Option<usize>) -> Vec<Match> {
+    let config = SearchConfig::builder("target.txt")
+        .start_dirs(roots.to_vec())
+        .mode(SearchMode::File)
+        .max_depth(max_depth)
+        .threads(2)
+        .build();
+    let mut found = SearchEngine::new(config).into_results().next_page(usize::MAX);
+    found.sort_by(|a, b| a.path.cmp(&b.path));
+    found
+}
+
+fn relative(found: &[Match], tmp: &Path) -> Vec<PathBuf> {
+    found.iter().map(|found| found.path.strip_prefix(tmp).unwrap().to_path_buf()).collect()
+}
+
+#[test]
+fn a_nested_root_counts_depth_from_itself() {
+    let tmp = make_tree("depth");
+    let (outer, inner) = (tmp.join("a"), tmp.join("a/b/c"));
+    
+    for roots in [vec![outer.clone(), inner.clone()], vec![inner.clone(), outer.clone()]] {
+        let found = search(&roots, Some(1));
+        assert_eq!(relative(&found, &tmp), [PathBuf::from("a/b/c/x/target.txt")]);
+        assert_eq!(found[0].root.as_deref(), Some(inner.as_path()));
+    }
+    assert_eq!(search(std::slice::from_ref(&inner), Some(1)).len(), search(&[outer, inner], Some(1)).len());
+}
+
+#[test]
+fn overlapping_roots_find_each_file_once() {
+    let tmp = make_tree("once");
+    let (outer, inner) = (tmp.join("a"), tmp.join("a/b/c"));
+    
+    let found = search(&[outer.clone(), inner.clone()], Some(5));
+    assert_eq!(relative(&found, &tmp), [PathBuf::from("a/b/c/x/target.txt"), PathBuf::from("a/b/c/x/y/target.txt")]);
+    assert!(found.iter().all(|found| found.root.as_deref() == Some(inner.as_path())));
+}
@@ -0,0 +1,71 @@
+//! A symlink's owner, group and permissions are the link's own, as with
+//! `find`, while its size and contents are what it points to.
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::{symlink, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use filesearch_core::{Expression, PermissionFilter, SearchConfig, SearchConfigBuilder, SearchEngine, SearchMode};
+
+// A private file and a link to it, whose own mode is whatever the system
+// gives links (0777 on Linux, 0755 on macOS)
+fn make_tree(name: &str) -> PathBuf {
+    let root = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("symlinks").join(name);
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    
+    let target = root.join("secret.txt");
+    fs::write(&target, "hidden").unwrap();
+    fs::set_permissions(&target, fs::Permissions::from_mode(0o600)).unwrap();
+    symlink(&target, root.join("link.txt")).unwrap();
+    root
+}
+
+fn found(config: SearchConfigBuilder) -> Vec<String> {
+    let mut names: Vec<String> = SearchEngine::new(config.build()).into_results().next_page(usize::MAX)
+        .into_iter()
+        .map(|found| found.path.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+fn config(root: &Path, query: &str) -> SearchConfigBuilder {
+    SearchConfig::builder(query).start_dir(root).mode(SearchMode::File).threads(1)
+}
+
+fn link_mode(root: &Path) -> u32 {
+    fs::symlink_metadata(root.join("link.txt")).unwrap().mode() & 0o7777
+}
+
+#[test]
+fn permissions_are_the_links_own() {
+    let root = make_tree("perm");
+    let private = Some(PermissionFilter::parse("600").unwrap());
+    assert_eq!(found(config(&root, "*").permissions(private)), ["secret.txt"]);
+    
+    let links = PermissionFilter::parse(&format!("{:o}", link_mode(&root))).unwrap();
+    assert_eq!(found(config(&root, "*").permissions(Some(links))), ["link.txt"]);
+}
+
+#[test]
+fn expressions_see_the_same_modes() {
+    let root = make_tree("expr");
+    let expression = |source: &str| Some(Expression::parse(source).unwrap());
+    
+    assert_eq!(found(config(&root, "").expression(expression("perm:600"))), ["secret.txt"]);
+    assert_eq!(found(config(&root, "").expression(expression(&format!("perm:{:o}", link_mode(&root))))), ["link.txt"]);
+    // Size still follows the link
+    assert_eq!(found(config(&root, "").expression(expression("size=6"))), ["link.txt", "secret.txt"]);
+}
+
+#[test]
+fn owners_are_the_links_own() {
+    let root = make_tree("owner");
+    let link = fs::symlink_metadata(root.join("link.txt")).unwrap();
+    
+    assert_eq!(found(config(&root, "link.txt").user(Some(link.uid()))), ["link.txt"]);
+    assert_eq!(found(config(&root, "link.txt").group(Some(link.gid()))), ["link.txt"]);
+    assert!(found(config(&root, "link.txt").user(Some(link.uid().wrapping_add(1)))).is_empty());
+}
@@ -0,0 +1,121 @@
+/*
+ * filesearch.h - C interface to the FileSearch engine.
+ *
+ * Link with -lfilesearch_ffi. A search is configured with patterns and
+ * options, then started by the first call to filesearch_search_next_result,
+ * which hands back matches one at a time as the worker threads find them.
+ *
+ *     filesearch_search *search = filesearch_search_new();
+ *     filesearch_search_add_pattern(search, "*.c");
+ *     filesearch_search_set_option(search, "dir", "/usr/src");
+ *
+ *     filesearch_result result;
+ *     while (filesearch_search_next_result(search, &result) == 1)
+ *         printf("%s\n", result.path);
+ *
+ *     filesearch_search_free(search);
+ */
+#ifndef FILESEARCH_H
+#define FILESEARCH_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Return codes. filesearch_search_next_result also returns 1 for a match. */
+#define FILESEARCH_OK                    0
+#define FILESEARCH_ERROR_NULL           -1 /* a required pointer was NULL */
+#define FILESEARCH_ERROR_UNKNOWN_OPTION -2
+#define FILESEARCH_ERROR_INVALID_VALUE  -3 /* bad option value, or a pattern that isn't UTF-8 */
+#define FILESEARCH_ERROR_STARTED        -4 /* the search is already running */
+#define FILESEARCH_ERROR_NO_PATTERN     -5 /* nothing to search for */
+
+typedef struct filesearch_search filesearch_search;
+
+typedef struct {
+    /* NUL-terminated; valid until the next call with the same search */
+    const char *path;
+    /* 1 for a directory, 0 for anything else */
+    int is_dir;
+} filesearch_result;
+
+/* Creates a search. Free it with filesearch_search_free. */
+filesearch_search *filesearch_search_new(void);
+
+/*
+ * Adds a name to look for: an exact name, or a pattern using * and ?.
+ * A path matches if its name fits any of the patterns.
+ */
+int filesearch_search_add_pattern(filesearch_search *search, const char *pattern);
+
+/*
+ * Sets an option before the search starts:
+ *
+ *   "dir"             a directory to search from; may be given more than
+ *                     once (default: the current directory)
+ *   "mode"            "file", "dir" or "both" (default)
+ *   "threads"         number of worker threads (default: CPU cores)
+ *   "case-sensitive"  "true" (default) or "false"; case is folded the
+ *                     Unicode way, so "straße" matches "STRASSE"
+ *   "smart-case"      "true" to ignore case unless a pattern has an
+ *                     uppercase letter, or "false" (default)
+ *   "normalize"       "nfc", "nfd" or "none" (default): the Unicode form
+ *                     names and patterns are compared in
+ *   "ignore-accents"  "true" to ignore accents and spell out ligatures, so
+ *                     "Resume*" matches "Résumé.docx", or "false" (default)
+ *   "wildcards"       "true" (default), or "false" to treat * and ? literally
+ *   "fuzzy"           "true" to match names containing the patterns' letters
+ *                     in order, or "false" (default)
+ *   "match-on"        what patterns are matched against: "name" (default),
+ *                     "path", "relpath" (below the start directory), "stem"
+ *                     (name without extension) or "ext"
+ *   "ext"             comma-separated extensions a name must end in, e.g.
+ *                     "rs,toml"; compared case-insensitively
+ *   "type"            comma-separated kinds to match, as in find -type: "f"
+ *                     file, "d" directory, "l" symlink, "p" pipe, "s" socket,
+ *                     "b" block and "c" character device
+ *   "empty"           "true" to only match empty files and directories
+ *   "executable"      "true" to only match files with execute permission
+ *   "user", "group"   only match entries with this owner or group, by name
+ *                     or ID (Unix only)
+ *   "nouser"          "true" to only match entries whose owner has no account
+ *   "nogroup"         "true" to only match entries whose group doesn't exist
+ *   "perm"            permission bits as for `filesearch --perm`: "644",
+ *                     "-4000" (all of these bits) or "/o+w" (any of them)
+ *   "mime"            comma-separated MIME types a file's first bytes must
+ *                     show one of, e.g. "image/png,application/pdf"; may use
+ *                     the * and ? wildcards
+ *   "kind"            the same by short name, e.g. "pdf,png,zip"
+ *   "expression"      match with an expression instead of the patterns, e.g.
+ *                     "name:*.log AND size>10M AND mtime<7d"; the syntax is
+ *                     described in `filesearch --help`
+ *   "depth"           levels below each start directory to descend
+ *   "order"           "bfs" (default) or "dfs"
+ *   "timeout-ms"      stop after this many milliseconds
+ *   "log"             append matches and a summary to this file
+ *   "checkpoint"      periodically save progress to this file
+ */
+int filesearch_search_set_option(filesearch_search *search, const char *name, const char *value);
+
+/*
+ * Waits for the next match and stores it in *result. Returns 1 for a match,
+ * 0 once the search has finished or been cancelled, or a negative error code.
+ * The first call starts the search.
+ */
+int filesearch_search_next_result(filesearch_search *search, filesearch_result *result);
+
+/*
+ * Stops the search; matches already found are still returned. Unlike the
+ * other functions this may be called from any thread, e.g. while another
+ * thread waits in filesearch_search_next_result.
+ */
+void filesearch_search_cancel(const filesearch_search *search);
+
+/* Cancels the search if it's running and releases it. NULL is ignored. */
+void filesearch_search_free(filesearch_search *search);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* FILESEARCH_H */
@@ -0,0 +1,309 @@
+//! C bindings for the FileSearch engine. The interface is declared in
+//! `include/filesearch.h`; see there for how each function behaves.
+
+use std::env;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use filesearch_core::{mime_for_kind, Expression, FileKind, MatchTarget, Normalization, PermissionFilter, Results, SearchConfig, SearchConfigBuilder, SearchEngine, SearchMode};
+
+pub const FILESEARCH_OK: c_int = 0;
+pub const FILESEARCH_ERROR_NULL: c_int = -1;
+pub const FILESEARCH_ERROR_UNKNOWN_OPTION: c_int = -2;
+pub const FILESEARCH_ERROR_INVALID_VALUE: c_int = -3;
+pub const FILESEARCH_ERROR_STARTED: c_int = -4;
+pub const FILESEARCH_ERROR_NO_PATTERN: c_int = -5;
+
+/// `filesearch_search` in C. Everything but the cancel flag sits behind a
+/// mutex, so `filesearch_search_cancel` can run while another thread is
+/// waiting for a result.
+pub struct Search {
+    cancelled: Arc<AtomicBool>,
+    inner: Mutex<SearchState>,
+}
+
+struct SearchState {
+    patterns: Vec<String>,
+    // Taken when the search starts
+    builder: Option<SearchConfigBuilder>,
+    has_start_dir: bool,
+    // An expression stands in for the patterns
+    has_expression: bool,
+    results: Option<Results>,
+    // Backs the path handed out by the last filesearch_search_next_result
+    current: Option<CString>,
+}
+
+/// `filesearch_result` in C.
+#[repr(C)]
+pub struct SearchResult {
+    pub path: *const c_char,
+    pub is_dir: c_int,
+}
+
+fn path_from_c(value: &CStr) -> Option<PathBuf> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Some(PathBuf::from(std::ffi::OsStr::from_bytes(value.to_bytes())))
+    }
+    
+    #[cfg(not(unix))]
+    {
+        value.to_str().ok().map(PathBuf::from)
+    }
+}
+
+fn path_to_c(path: &std::path::Path) -> CString {
+    #[cfg(unix)]
+    let bytes = {
+        use std::os::unix::ffi::OsStrExt;
+        path.as_os_str().as_bytes().to_vec()
+    };
+    
+    #[cfg(not(unix))]
+    let bytes = path.to_string_lossy().into_owned().into_bytes();
+    
+    // Paths can't contain NUL bytes, so this only guards against the impossible
+    CString::new(bytes).unwrap_or_default()
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn apply_option(builder: SearchConfigBuilder, name: &str, value: &CStr) -> Result<SearchConfigBuilder, c_int> {
+    let text = || value.to_str().map_err(|_| FILESEARCH_ERROR_INVALID_VALUE);
+    let invalid = |_| FILESEARCH_ERROR_INVALID_VALUE;
+    
+    let builder = match name {
+        "dir" => builder.start_dir(path_from_c(value).ok_or(FILESEARCH_ERROR_INVALID_VALUE)?),
+        "mode" => builder.mode(match text()? {
+            "file" => SearchMode::File,
+            "dir" | "directory" => SearchMode::Directory,
+            "both" => SearchMode::Both,
+            _ => return Err(FILESEARCH_ERROR_INVALID_VALUE),
+        }),
+        "threads" => match text()?.parse() {
+            Ok(threads) if threads > 0 => builder.threads(threads),
+            _ => return Err(FILESEARCH_ERROR_INVALID_VALUE),
+        },
+        "case-sensitive" => builder.case_sensitive(parse_bool(text()?).ok_or(FILESEARCH_ERROR_INVALID_VALUE)?),
+        "smart-case" => builder.smart_case(parse_bool(text()?).ok_or(FILESEARCH_ERROR_INVALID_VALUE)?),
+        "normalize" => builder.normalization(match text()? {
+            "nfc" => Normalization::Nfc,
+            "nfd" => Normalization::Nfd,
+            "none" => Normalization::None,
+            _ => return Err(FILESEARCH_ERROR_INVALID_VALUE),
+        }),
+        "ignore-accents" => builder.ignore_accents(parse_bool(text()?).ok_or(FILESEARCH_ERROR_INVALID_VALUE)?),
+        "wildcards" => builder.wildcards(parse_bool(text()?).ok_or(FILESEARCH_ERROR_INVALID_VALUE)?),
+        "fuzzy" => builder.fuzzy(parse_bool(text()?).ok_or(FILESEARCH_ERROR_INVALID_VALUE)?),
+        "match-on" => builder.match_on(match text()? {
+            "name" => MatchTarget::Name,
+            "path" => MatchTarget::Path,
+            "relpath" => MatchTarget::RelativePath,
+            "stem" => MatchTarget::Stem,
+            "ext" => MatchTarget::Extension,
+            _ => return Err(FILESEARCH_ERROR_INVALID_VALUE),
+        }),
+        "ext" => builder.extensions(text()?.split(',').filter(|extension| !extension.is_empty()).map(str::to_string).collect()),
+        "type" => builder.file_kinds(text()?.split(',')
+            .filter(|letter| !letter.is_empty())
+            .map(|letter| {
+                let mut chars = letter.chars();
+                chars.next().and_then(FileKind::from_letter).filter(|_| chars.next().is_none())
+            })
+            .collect::<Option<_>>()
+            .ok_or(FILESEARCH_ERROR_INVALID_VALUE)?),
+        "empty" => builder.only_empty(parse_bool(text()?).ok_or(FILESEARCH_ERROR_INVALID_VALUE)?),
+        "executable" => builder.only_executable(parse_bool(text()?).ok_or(FILESEARCH_ERROR_INVALID_VALUE)?),
+        #[cfg(unix)]
+        "user" => builder.user(Some(filesearch_core::user_id(text()?).ok_or(FILESEARCH_ERROR_INVALID_VALUE)?)),
+        #[cfg(unix)]
+        "group" => builder.group(Some(filesearch_core::group_id(text()?).ok_or(FILESEARCH_ERROR_INVALID_VALUE)?)),
+        "nouser" => builder.no_user(parse_bool(text()?).ok_or(FILESEARCH_ERROR_INVALID_VALUE)?),
+        "nogroup" => builder.no_group(parse_bool(text()?).ok_or(FILESEARCH_ERROR_INVALID_VALUE)?),
+        "perm" => builder.permissions(Some(PermissionFilter::parse(text()?).map_err(|_| FILESEARCH_ERROR_INVALID_VALUE)?)),
+        "mime" => builder.mime_types(text()?.split(',').filter(|mime| !mime.is_empty()).map(str::to_string).collect()),
+        "kind" => builder.mime_types(text()?.split(',')
+            .filter(|kind| !kind.is_empty())
+            .map(|kind| mime_for_kind(kind).map(str::to_string))
+            .collect::<Option<_>>()
+            .ok_or(FILESEARCH_ERROR_INVALID_VALUE)?),
+        "expression" => builder.expression(Some(Expression::parse(text()?).map_err(|_| FILESEARCH_ERROR_INVALID_VALUE)?)),
+        "depth" => builder.max_depth(Some(text()?.parse().map_err(invalid)?)),
+        "order" => builder.breadth_first(match text()? {
+            "bfs" => true,
+            "dfs" => false,
+            _ => return Err(FILESEARCH_ERROR_INVALID_VALUE),
+        }),
+        "timeout-ms" => builder.timeout(Some(Duration::from_millis(text()?.parse().map_err(invalid)?))),
+        "log" => builder.log_path(Some(path_from_c(value).ok_or(FILESEARCH_ERROR_INVALID_VALUE)?)),
+        "checkpoint" => builder.checkpoint_path(Some(path_from_c(value).ok_or(FILESEARCH_ERROR_INVALID_VALUE)?)),
+        _ => return Err(FILESEARCH_ERROR_UNKNOWN_OPTION),
+    };
+    
+    Ok(builder)
+}
+
+/// Creates a search; free it with `filesearch_search_free`.
+#[no_mangle]
+pub extern "C" fn filesearch_search_new() -> *mut Search {
+    let search = Search {
+        cancelled: Arc::new(AtomicBool::new(false)),
+        inner: Mutex::new(SearchState {
+            patterns: Vec::new(),
+            builder: Some(SearchConfig::builder("")),
+            has_start_dir: false,
+            has_expression: false,
+            results: None,
+            current: None,
+        }),
+    };
+    
+    Box::into_raw(Box::new(search))
+}
+
+/// # Safety
+///
+/// `search` must come from `filesearch_search_new` and not have been freed;
+/// `pattern` must be NULL or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn filesearch_search_add_pattern(search: *mut Search, pattern: *const c_char) -> c_int {
+    if search.is_null() || pattern.is_null() {
+        return FILESEARCH_ERROR_NULL;
+    }
+    let search = &*search;
+    let pattern = match CStr::from_ptr(pattern).to_str() {
+        Ok(pattern) => pattern.to_string(),
+        Err(_) => return FILESEARCH_ERROR_INVALID_VALUE,
+    };
+    
+    let mut state = search.inner.lock().unwrap();
+    if state.results.is_some() {
+        return FILESEARCH_ERROR_STARTED;
+    }
+    state.patterns.push(pattern);
+    FILESEARCH_OK
+}
+
+/// # Safety
+///
+/// `search` must come from `filesearch_search_new` and not have been freed;
+/// `name` and `value` must be NULL or NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn filesearch_search_set_option(search: *mut Search, name: *const c_char, value: *const c_char) -> c_int {
+    if search.is_null() || name.is_null() || value.is_null() {
+        return FILESEARCH_ERROR_NULL;
+    }
+    let search = &*search;
+    let name = match CStr::from_ptr(name).to_str() {
+        Ok(name) => name,
+        Err(_) => return FILESEARCH_ERROR_UNKNOWN_OPTION,
+    };
+    let value = CStr::from_ptr(value);
+    
+    let mut state = search.inner.lock().unwrap();
+    let builder = match state.builder.take() {
+        Some(builder) => builder,
+        None => return FILESEARCH_ERROR_STARTED,
+    };
+    
+    match apply_option(builder.clone(), name, value) {
+        Ok(builder) => {
+            state.builder = Some(builder);
+            state.has_start_dir |= name == "dir";
+            state.has_expression |= name == "expression";
+            FILESEARCH_OK
+        }
+        Err(code) => {
+            state.builder = Some(builder);
+            code
+        }
+    }
+}
+
+/// # Safety
+///
+/// `search` must come from `filesearch_search_new` and not have been freed;
+/// `result` must be NULL or point to writable memory for a `filesearch_result`.
+#[no_mangle]
+pub unsafe extern "C" fn filesearch_search_next_result(search: *mut Search, result: *mut SearchResult) -> c_int {
+    if search.is_null() || result.is_null() {
+        return FILESEARCH_ERROR_NULL;
+    }
+    let search = &*search;
+    let mut state = search.inner.lock().unwrap();
+    
+    if state.results.is_none() {
+        let (query, alternatives) = match state.patterns.split_first() {
+            Some((query, alternatives)) => (query.clone(), alternatives.to_vec()),
+            None if state.has_expression => (String::new(), Vec::new()),
+            None => return FILESEARCH_ERROR_NO_PATTERN,
+        };
+        
+        let mut builder = state.builder.take()
+            .expect("builder is only taken when the search starts")
+            .query(query)
+            .alternatives(alternatives);
+        if !state.has_start_dir {
+            builder = builder.start_dir(env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
+        }
+        
+        let mut engine = SearchEngine::new(builder.build());
+        engine.set_cancel_flag(search.cancelled.clone());
+        state.results = Some(engine.into_results());
+    }
+    
+    let found = state.results.as_mut().and_then(|results| results.next());
+    match found {
+        Some(found) => {
+            let path = path_to_c(&found.path);
+            (*result).path = path.as_ptr();
+            (*result).is_dir = found.is_dir as c_int;
+            state.current = Some(path);
+            1
+        }
+        None => {
+            (*result).path = ptr::null();
+            (*result).is_dir = 0;
+            state.current = None;
+            0
+        }
+    }
+}
+
+/// # Safety
+///
+/// `search` must be NULL or come from `filesearch_search_new` and not have
+/// been freed.
+#[no_mangle]
+pub unsafe extern "C" fn filesearch_search_cancel(search: *const Search) {
+    if let Some(search) = search.as_ref() {
+        search.cancelled.store(true, Ordering::SeqCst);
+    }
+}
+
+/// # Safety
+///
+/// `search` must be NULL or come from `filesearch_search_new`, and must not
+/// be used again afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn filesearch_search_free(search: *mut Search) {
+    if search.is_null() {
+        return;
+    }
+    let search = Box::from_raw(search);
+    // Dropping the results stops and joins the search threads
+    search.cancelled.store(true, Ordering::SeqCst);
+    drop(search);
+}
@@ -0,0 +1,94 @@
+/*
+ * Exercises the C interface against a directory tree made by the Rust
+ * test: search_test DIR EXPECTED_TXT_FILES
+ */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "filesearch.h"
+
+static int failures = 0;
+
+#define CHECK(cond) do { \
+        if (!(cond)) { \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); \
+            failures++; \
+        } \
+    } while (0)
+
+static int count_results(filesearch_search *search, int *dirs) {
+    filesearch_result result;
+    int count = 0;
+    int status;
+
+    while ((status = filesearch_search_next_result(search, &result)) == 1) {
+        CHECK(result.path != NULL && strlen(result.path) > 0);
+        if (result.is_dir)
+            (*dirs)++;
+        count++;
+    }
+    CHECK(status == 0);
+    CHECK(result.path == NULL);
+    return count;
+}
+
+int main(int argc, char **argv) {
+    if (argc != 3) {
+        fprintf(stderr, "usage: %s DIR EXPECTED_TXT_FILES\n", argv[0]);
+        return 2;
+    }
+    const char *dir = argv[1];
+    int expected = atoi(argv[2]);
+    int dirs = 0;
+
+    /* Files matching either of two patterns */
+    filesearch_search *search = filesearch_search_new();
+    CHECK(search != NULL);
+    CHECK(filesearch_search_add_pattern(search, "*.txt") == FILESEARCH_OK);
+    CHECK(filesearch_search_add_pattern(search, "notes") == FILESEARCH_OK);
+    CHECK(filesearch_search_set_option(search, "dir", dir) == FILESEARCH_OK);
+    CHECK(filesearch_search_set_option(search, "mode", "file") == FILESEARCH_OK);
+    CHECK(filesearch_search_set_option(search, "threads", "2") == FILESEARCH_OK);
+    CHECK(count_results(search, &dirs) == expected + 1);
+    CHECK(dirs == 0);
+    CHECK(filesearch_search_set_option(search, "depth", "1") == FILESEARCH_ERROR_STARTED);
+    filesearch_search_free(search);
+
+    /* Bad options leave the search usable */
+    search = filesearch_search_new();
+    CHECK(filesearch_search_set_option(search, "colour", "blue") == FILESEARCH_ERROR_UNKNOWN_OPTION);
+    CHECK(filesearch_search_set_option(search, "mode", "sideways") == FILESEARCH_ERROR_INVALID_VALUE);
+    CHECK(filesearch_search_set_option(search, "threads", "0") == FILESEARCH_ERROR_INVALID_VALUE);
+    CHECK(filesearch_search_set_option(search, NULL, "1") == FILESEARCH_ERROR_NULL);
+    filesearch_result result;
+    CHECK(filesearch_search_next_result(search, &result) == FILESEARCH_ERROR_NO_PATTERN);
+    CHECK(filesearch_search_add_pattern(search, "SUB*") == FILESEARCH_OK);
+    CHECK(filesearch_search_set_option(search, "dir", dir) == FILESEARCH_OK);
+    CHECK(filesearch_search_set_option(search, "mode", "dir") == FILESEARCH_OK);
+    CHECK(filesearch_search_set_option(search, "case-sensitive", "false") == FILESEARCH_OK);
+    dirs = 0;
+    CHECK(count_results(search, &dirs) == 2);
+    CHECK(dirs == 2);
+    filesearch_search_free(search);
+
+    /* A search cancelled before it starts finds nothing */
+    search = filesearch_search_new();
+    CHECK(filesearch_search_add_pattern(search, "*") == FILESEARCH_OK);
+    CHECK(filesearch_search_set_option(search, "dir", dir) == FILESEARCH_OK);
+    filesearch_search_cancel(search);
+    CHECK(count_results(search, &dirs) == 0);
+    filesearch_search_free(search);
+
+    /* Freeing a search that was never run, or NULL, is fine */
+    filesearch_search_free(filesearch_search_new());
+    filesearch_search_free(NULL);
+    filesearch_search_cancel(NULL);
+
+    if (failures > 0) {
+        fprintf(stderr, "%d check(s) failed\n", failures);
+        return 1;
+    }
+    printf("all checks passed\n");
+    return 0;
+}
@@ -0,0 +1,62 @@
+//! Builds tests/c/search_test.c against the cdylib and runs it, so the
+//! header and the exported symbols are checked from real C.
+
+#![cfg(unix)]
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const TXT_FILES: usize = 4;
+
+fn make_tree(root: &Path) {
+    let _ = fs::remove_dir_all(root);
+    fs::create_dir_all(root.join("sub1").join("deeper")).unwrap();
+    fs::create_dir_all(root.join("Sub2")).unwrap();
+    
+    for file in ["a.txt", "sub1/b.txt", "sub1/deeper/c.txt", "Sub2/d.txt", "sub1/notes", "e.md"] {
+        fs::write(root.join(file), "x").unwrap();
+    }
+}
+
+// Integration tests are built into target/<profile>/deps, next to the cdylib
+fn library_dir() -> PathBuf {
+    let exe = env::current_exe().unwrap();
+    exe.parent().unwrap().to_path_buf()
+}
+
+#[test]
+fn c_program_runs_against_the_library() {
+    let tmp = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("ffi");
+    let tree = tmp.join("tree");
+    make_tree(&tree);
+    
+    let lib_dir = library_dir();
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let program = tmp.join("search_test");
+    let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    
+    let status = Command::new(&compiler)
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg(manifest_dir.join("tests").join("c").join("search_test.c"))
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lfilesearch_ffi")
+        .arg("-o")
+        .arg(&program)
+        .status()
+        .unwrap_or_else(|e| panic!("cannot run {}: {}", compiler, e));
+    assert!(status.success(), "compiling the C test program failed");
+    
+    let output = Command::new(&program)
+        .arg(&tree)
+        .arg(TXT_FILES.to_string())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "C test program failed:\n{}", String::from_utf8_lossy(&output.stderr));
+}